@@ -1,3 +1,4 @@
+import { UnlistenFn } from "@tauri-apps/api/event";
 type BytesDto = string | number[];
 export type ClientPath = string | Iterable<number> | ArrayLike<number> | ArrayBuffer;
 export type VaultPath = string | Iterable<number> | ArrayLike<number> | ArrayBuffer;
@@ -50,6 +51,98 @@ export interface Duration {
     /** The fractional part of this Duration, in nanoseconds. Must be greater or equal to 0 and smaller than 1e+9 (the max number of nanoseoncds in a second)*/
     nanos: number;
 }
+/** The argon2 cost parameters used to derive the snapshot key from the password. */
+export interface Argon2Config {
+    /** Memory usage in KiB. */
+    memCost: number;
+    /** Number of passes over the memory. */
+    timeCost: number;
+    /** Degree of parallelism. */
+    lanes: number;
+    variant: "Argon2d" | "Argon2i" | "Argon2id";
+}
+/** A background task spawned by the plugin, see {@link Stronghold.activeTasks}. */
+export interface TaskInfo {
+    /** `AutoCommit` for a save scheduled by `Builder::with_commit_debounce`. */
+    kind: "AutoCommit";
+    /** The time left until the task runs. */
+    dueIn: Duration;
+}
+/** A key derived by {@link Vault.scanAddresses}. */
+export interface DerivedAddress {
+    index: number;
+    /** The full derivation chain of the key, hardened. */
+    chain: number[];
+    /** The Ed25519 public key, to be encoded as an address. */
+    publicKey: Uint8Array;
+}
+/** The outcome of {@link Stronghold.cryptoSelfTest}. */
+export interface SelfTestReport {
+    /** Whether all tests passed. */
+    passed: boolean;
+    results: Array<{
+        algorithm: string;
+        passed: boolean;
+        /** Why the test could not be run, if it failed with an error rather than a wrong answer. */
+        error: string | null;
+    }>;
+}
+/** A failed automatic save, see {@link Stronghold.onCommitError}. */
+export interface CommitError {
+    snapshotPath: string;
+    reason: string;
+}
+/** Whether a snapshot is loaded, and if so whether it was locked because too many snapshots were loaded. */
+export type SnapshotState = "NotLoaded" | "Locked" | "Unlocked";
+/** The outcome of checking a snapshot for an interrupted save. */
+export type SnapshotRecovery = "NotNeeded" | "Completed" | "RolledBack";
+/** The result of {@link Stronghold.verifyIntegrity} for one client. */
+export interface ClientIntegrity {
+    client: BytesDto;
+    /** Whether the client exists in the snapshot. */
+    loaded: boolean;
+    /** The number of store entries, without the plugin's own entries. */
+    storeEntries: number;
+    /** The store keys whose value could not be read. */
+    unreadableStoreKeys: number[][];
+    /** The records written by the key generation APIs that are missing, as `vault/record` with hex encoded paths. */
+    missingRecords: string[];
+}
+export interface IntegrityReport {
+    /** Whether all clients were found and no problem was detected. */
+    ok: boolean;
+    clients: ClientIntegrity[];
+}
+/** Non-secret information about a snapshot, see {@link Stronghold.debugInfo}. */
+export interface SnapshotDebugInfo {
+    /** The major version of the Stronghold library the plugin was built against, which determines the snapshot format. */
+    strongholdVersion: string;
+    pluginVersion: string;
+    snapshotExists: boolean;
+    snapshotSize: number | null;
+    initialized: boolean;
+    /** The argon2 parameters stored for the snapshot, if any. */
+    kdf: {
+        memCost: number;
+        timeCost: number;
+        lanes: number;
+        variant: string;
+    } | null;
+    /** The requested clients, keyed by their path, hex encoded unless given as a string. */
+    clients: Record<string, {
+        loaded: boolean;
+        /** The number of store entries, `null` if the client is not loaded. */
+        storeKeys: number | null;
+        /** The number of records written by the key generation methods. */
+        typedRecords: number | null;
+    }>;
+}
+export interface VersionInfo {
+    /** The version of the plugin crate. */
+    plugin: string;
+    /** The version of the Stronghold library the plugin was built against. */
+    stronghold: string;
+}
 export declare class Location {
     type: string;
     payload: Record<string, unknown>;
@@ -57,6 +150,53 @@ export declare class Location {
     static generic(vault: VaultPath, record: RecordPath): Location;
     static counter(vault: VaultPath, counter: number): Location;
 }
+export type PublicKeyFormat = "raw" | "hex" | "base64";
+/** The languages of the BIP39 wordlists bundled with the plugin. */
+export type Bip39Language = "English" | "Japanese";
+/** The AEAD algorithms, see {@link Stronghold.generateNonce}. */
+export type AeadAlgorithm = "Aes256Gcm" | "XChaCha20Poly1305";
+/** The kind of key held by a record written by one of the key generation APIs. */
+export type KeyKind = "Seed" | "Ed25519" | "X25519" | "Symmetric" | "Secp256k1";
+/** The hash applied to a message before it is signed with secp256k1: `Keccak256` for Ethereum, `Sha256` for Bitcoin. */
+export type Secp256k1Hash = "Keccak256" | "Sha256";
+/** A signed set of public keys, see {@link Vault.attestPublicKeys}. */
+export interface Attestation {
+    /** `Secp256k1Sha256` is an ECDSA signature over the SHA-256 hash of the message. */
+    algorithm: "Ed25519" | "Secp256k1Sha256";
+    signingPublicKey: Uint8Array;
+    keys: Array<{
+        location: Location;
+        kind: KeyKind;
+        publicKey: Uint8Array;
+    }>;
+    /** The signed message: a context string followed by the kind, the big endian 16-bit length and the bytes of each public key. */
+    message: Uint8Array;
+    signature: Uint8Array;
+}
+/** The party information mixed into the key derived by {@link Vault.concatKdf}. */
+export interface PartyInfo {
+    /** Information about the initiating party (`apu`). */
+    partyU?: number[];
+    /** Information about the responding party (`apv`). */
+    partyV?: number[];
+    /** Supplementary public information. */
+    public?: number[];
+    /** Supplementary private information. */
+    private?: number[];
+}
+/** A procedure as expected by {@link ProcedureExecutor.executeProcedures}. */
+export interface Procedure {
+    type: string;
+    payload: Record<string, unknown>;
+}
+/** The error returned when one of the procedures of a batch fails. */
+export interface ProcedureStepError {
+    /** The index of the failing procedure. */
+    step: number;
+    /** The type of the failing procedure. */
+    procedure: string;
+    message: string;
+}
 declare class ProcedureExecutor {
     procedureArgs: Record<string, unknown>;
     constructor(procedureArgs: Record<string, unknown>);
@@ -72,7 +212,7 @@ declare class ProcedureExecutor {
      * Derive a SLIP10 private key using a seed or key.
      * @param chain The chain path.
      * @param source The source type, either 'Seed' or 'Key'.
-     * @param sourceLocation The source location, must be the `outputLocation` of a previous call to `generateSLIP10Seed` or `deriveSLIP10`.
+     * @param sourceLocation The source location, must be the `outputLocation` of a previous call to `generateSLIP10Seed`, `recoverBIP39`, `generateBIP39` or `Vault.importSeed` for a 'Seed' source, or of `deriveSLIP10` for a 'Key' source.
      * @param outputLocation Location of the record where the private key will be stored.
      * @param hint The record hint.
      * @returns
@@ -101,6 +241,13 @@ declare class ProcedureExecutor {
      * @returns A promise resolving to the public key hex string.
      */
     getEd25519PublicKey(privateKeyLocation: Location): Promise<Uint8Array>;
+    /**
+     * Gets the Ed25519 public key of a SLIP10 private key encoded in the given format.
+     * @param privateKeyLocation The location of the private key. Must be the `outputLocation` of a previous call to `deriveSLIP10`.
+     * @param format The encoding of the returned public key.
+     * @returns A promise resolving to the public key bytes, or its hex or base64 string.
+     */
+    exportPublicKey(privateKeyLocation: Location, format: PublicKeyFormat): Promise<Uint8Array | string>;
     /**
      * Creates a Ed25519 signature from a private key.
      * @param privateKeyLocation The location of the record where the private key is stored. Must be the `outputLocation` of a previous call to `deriveSLIP10`.
@@ -108,6 +255,14 @@ declare class ProcedureExecutor {
      * @returns A promise resolving to the signature hex string.
      */
     signEd25519(privateKeyLocation: Location, msg: string): Promise<Uint8Array>;
+    /**
+     * Executes a list of procedures in order, stopping at the first failure.
+     * On failure the promise is rejected with a {@link ProcedureStepError}, and the records written
+     * by the steps before the failed one are kept.
+     * @param procedures The procedures to execute.
+     * @returns A promise resolving to the output of each procedure.
+     */
+    executeProcedures(procedures: Procedure[]): Promise<Uint8Array[]>;
 }
 export declare class Client {
     path: string;
@@ -127,8 +282,32 @@ export declare class Store {
     client: BytesDto;
     constructor(path: string, client: BytesDto);
     get(key: StoreKey): Promise<Uint8Array | null>;
+    /**
+     * Get the values of several records at once.
+     * @param keys The record keys.
+     * @returns The values in the order of `keys`, `null` for records that do not exist.
+     */
+    getMany(keys: StoreKey[]): Promise<Array<Uint8Array | null>>;
     insert(key: StoreKey, value: number[], lifetime?: Duration): Promise<void>;
     remove(key: StoreKey): Promise<Uint8Array | null>;
+    /**
+     * List the keys of all non-expired records in the store.
+     * The record values are not returned.
+     * @returns
+     */
+    keys(): Promise<Uint8Array[]>;
+    /**
+     * Remove all records from the store, e.g. session data on sign out. The vaults of the client are not affected.
+     * @returns The number of records removed.
+     */
+    clear(): Promise<number>;
+    /**
+     * Iterate over the keys of all non-expired records in the store, fetching them in pages.
+     * The record values are not returned.
+     * @param pageSize The number of keys fetched at once, at least 1. Each page lists and sorts all keys of the store, so prefer large pages for large stores.
+     * @returns
+     */
+    scan(pageSize?: number): AsyncGenerator<Uint8Array>;
 }
 /**
  * A key-value storage that allows create, update and delete operations.
@@ -150,6 +329,143 @@ export declare class Vault extends ProcedureExecutor {
      * @returns
      */
     insert(recordPath: RecordPath, secret: number[]): Promise<void>;
+    /**
+     * Generate a random symmetric key for the AEAD procedures and store it in this vault.
+     * @param location The record location.
+     * @param size The key size in bytes, currently only 32 is supported.
+     * @returns
+     */
+    generateSecretKey(location: Location, size?: number): Promise<void>;
+    /**
+     * Compute the current time-based one-time password (TOTP, RFC 6238) with an HMAC key stored in this vault.
+     * The key never leaves the vault.
+     *
+     * **Standard SHA-1 TOTP secrets are not supported**, as the vault procedures have no HMAC-SHA1.
+     * Most authenticator apps and `otpauth://` URIs without an `algorithm` parameter use SHA-1, and their codes cannot be computed here.
+     * @param secretLocation The location of the HMAC key, e.g. written with {@link Vault.insert}.
+     * @param hash The HMAC hash the secret was issued for.
+     * @param timeStep The time step in seconds.
+     * @param digits The number of digits, 6 to 8.
+     * @returns
+     */
+    generateTotp(secretLocation: Location, hash: "Sha256" | "Sha512", timeStep?: number, digits?: number): Promise<string>;
+    /**
+     * Check whether a record holds the given secret, without reading the secret out of the vault.
+     * The comparison is done in constant time.
+     * @param location The record location.
+     * @param candidate The expected secret.
+     * @returns
+     */
+    recordMatches(location: Location, candidate: number[] | Uint8Array): Promise<boolean>;
+    /**
+     * Compute the key check value of a symmetric key, to verify it matches an expected key without exposing it.
+     * @param location The location of the key.
+     * @param algorithm The algorithm used to encrypt the check block.
+     * @returns The first 3 bytes of the ciphertext of a zero block encrypted under the key, without the authentication tag.
+     */
+    keyCheckValue(location: Location, algorithm?: AeadAlgorithm): Promise<Uint8Array>;
+    /**
+     * List the records written or removed through the plugin since the given time, e.g. to sync only the changed records.
+     * Removed records are included.
+     * @param since
+     * @returns The locations of the records.
+     */
+    recordsModifiedSince(since: Date): Promise<Location[]>;
+    /**
+     * Derive the Ed25519 public keys of consecutive indices of an account, e.g. to discover used addresses up to a gap limit.
+     * The derived private keys are not kept.
+     * @param seedLocation The location of the seed.
+     * @param account The derivation chain of the account, hardened, so each index must be below 2^31.
+     * @param gapLimit The number of indices to derive, from 1 to 1000.
+     * @param start The first index. The last derived index must be below 2^31 as well.
+     * @returns
+     */
+    scanAddresses(seedLocation: Location, account: number[], gapLimit: number, start?: number): Promise<DerivedAddress[]>;
+    /**
+     * Generate an X25519 key pair for WireGuard and store its private key in this vault.
+     * The private key never leaves the vault; Stronghold can use it for X25519 key exchanges inside the vault.
+     * @param location The record location.
+     * @returns The public key encoded in base64, as expected in WireGuard configurations.
+     */
+    generateWireguardKeypair(location: Location): Promise<string>;
+    /**
+     * Generate a secp256k1 private key and store it in this vault.
+     * @param location The record location.
+     * @returns
+     */
+    generateSecp256k1(location: Location): Promise<void>;
+    /**
+     * Get the compressed public key of a secp256k1 private key.
+     * @param location The location of the private key, written by {@link Vault.generateSecp256k1}.
+     * @returns
+     */
+    secp256k1PublicKey(location: Location): Promise<Uint8Array>;
+    /**
+     * Sign the public keys of several key pairs with a signing key, e.g. to register a device with a server.
+     * All keys must have been written by the key generation APIs. The signing key must be an Ed25519 or secp256k1 key.
+     * @param signingLocation The location of the signing key.
+     * @param keyLocations The locations of the key pairs to attest.
+     * @returns
+     */
+    attestPublicKeys(signingLocation: Location, keyLocations: Location[]): Promise<Attestation>;
+    /**
+     * Sign a message with a secp256k1 private key.
+     * @param location The location of the private key, written by {@link Vault.generateSecp256k1}.
+     * @param msg The message, hashed with `hash` before signing.
+     * @param hash The message hash.
+     * @returns The 65 byte recoverable signature, with the recovery id as last byte.
+     */
+    secp256k1Sign(location: Location, msg: number[] | Uint8Array, hash?: Secp256k1Hash): Promise<Uint8Array>;
+    /**
+     * Store a raw binary seed in this vault, to be used as the seed of {@link ProcedureExecutor.deriveSLIP10}.
+     * @param location The record location.
+     * @param seed The seed, 16 to 64 bytes long.
+     * @returns
+     */
+    importSeed(location: Location, seed: number[] | Uint8Array): Promise<void>;
+    /**
+     * Store an existing private key in this vault, e.g. when migrating from another tool.
+     * The key is validated first: malformed keys are rejected.
+     * @param location The record location.
+     * @param keyBytes The 32 byte private key, or an unencrypted PKCS#8 document in DER.
+     * @param keyType The algorithm of the key.
+     * @param format The encoding of `keyBytes`.
+     * @returns
+     */
+    importPrivateKey(location: Location, keyBytes: number[] | Uint8Array, keyType: "Ed25519" | "X25519" | "Secp256k1", format?: "Raw" | "Pkcs8"): Promise<void>;
+    /**
+     * Derive a key for the given context from a master key stored in the vault, using HKDF-SHA256.
+     * Different contexts yield independent keys, none of which leave the vault.
+     * @param masterLocation The location of the master key.
+     * @param context The purpose of the derived key, e.g. the name of the feature using it.
+     * @param outputLocation The location to store the derived key at.
+     * @returns
+     */
+    deriveKey(masterLocation: Location, context: number[] | Uint8Array, outputLocation: Location): Promise<void>;
+    /**
+     * Derive a 32 byte symmetric key from a shared secret stored in the vault, e.g. the output of an X25519 key exchange,
+     * using the NIST SP 800-56A concat KDF with SHA-256. The shared secret never leaves the vault.
+     * @param sharedSecretLocation The location of the shared secret.
+     * @param algorithmId The identifier of the algorithm the derived key is used with.
+     * @param partyInfo The party information mixed into the derived key.
+     * @param outputLocation The location to store the derived key at.
+     * @returns
+     */
+    concatKdf(sharedSecretLocation: Location, algorithmId: string, partyInfo: PartyInfo, outputLocation: Location): Promise<void>;
+    /**
+     * Insert the contents of a file as a record of this vault.
+     * The file is read by the plugin, so its contents never pass through the webview.
+     * @param location The record location.
+     * @param filePath The path of the file to read.
+     * @returns
+     */
+    insertFromFile(location: Location, filePath: string): Promise<void>;
+    /**
+     * Get the kind of key stored in a record.
+     * @param location The record location.
+     * @returns The key kind, or `null` if the record was not written by a key generation API.
+     */
+    recordType(location: Location): Promise<KeyKind | null>;
     /**
      * Remove a record from the vault.
      * @param location The record location.
@@ -172,20 +488,220 @@ export declare class Stronghold {
     private constructor();
     /**
      * Load the snapshot if it exists (password must match), or start a fresh stronghold instance otherwise.
+     * Relative paths are resolved in the namespace directory if the plugin is configured with `Builder::with_namespace`, and must not contain `..` components then.
+     * @param password
+     * @param salt An optional salt for this snapshot, only supported with the argon2 password hash.
+     * It is stored next to the snapshot and used whenever it is loaded without a salt.
+     * @returns
+     */
+    static load(path: string, password: string, salt?: number[]): Promise<Stronghold>;
+    /**
+     * Load the snapshot if it exists, or start a fresh stronghold instance otherwise, with a key reconstructed from Shamir shares instead of a password.
+     * The shares bypass the password hash, including the pepper configured with `Builder::with_pepper_provider`.
+     * @param path
+     * @param shares At least the threshold number of shares created by {@link Stronghold.splitKey}.
+     * @param createNew Whether to ignore an existing snapshot, see {@link Stronghold.createNew}.
+     * @returns
+     */
+    static loadWithShares(path: string, shares: Array<number[] | Uint8Array>, createNew?: boolean): Promise<Stronghold>;
+    /**
+     * Split a 32 byte snapshot key into Shamir shares, any `threshold` of which unlock the snapshot with {@link Stronghold.loadWithShares}.
+     * @param key
+     * @param threshold The number of shares needed to reconstruct the key.
+     * @param shares The number of shares to create, at most 255.
+     * @returns
+     */
+    static splitKey(key: number[] | Uint8Array, threshold: number, shares: number): Promise<Uint8Array[]>;
+    /**
+     * Reconstruct a key split by {@link Stronghold.splitKey}.
+     * Fewer shares than the threshold yield a wrong key rather than an error.
+     * @param shares
+     * @returns
+     */
+    static combineShares(shares: Array<number[] | Uint8Array>): Promise<Uint8Array>;
+    /**
+     * Start a fresh, empty stronghold instance, even if a snapshot exists at `path`.
+     * The existing snapshot and the files stored next to it, e.g. its salt, are deleted, e.g. to reset a corrupted snapshot or one whose first save was interrupted.
+     * @param path
      * @param password
+     * @param salt An optional salt for this snapshot, see {@link Stronghold.load}.
      * @returns
      */
-    static load(path: string, password: string): Promise<Stronghold>;
+    static createNew(path: string, password: string, salt?: number[]): Promise<Stronghold>;
+    /**
+     * Check the snapshot at `path` for a save that was interrupted, e.g. by a crash,
+     * and either complete it or discard the partially written file.
+     * This also happens automatically when the snapshot is loaded.
+     * If the first save of the snapshot was interrupted, or the password is wrong, this fails and the snapshot can only be started over with {@link Stronghold.createNew}.
+     * @param path
+     * @param password
+     * @returns
+     */
+    static recover(path: string, password: string): Promise<SnapshotRecovery>;
+    /**
+     * Check a password against the check stored next to the snapshot at `path`, without decrypting the snapshot.
+     * This gives faster feedback than {@link Stronghold.load} on large snapshots.
+     * @param path
+     * @param password
+     * @returns Whether the password is correct, or `null` if the snapshot was saved without a check.
+     */
+    static quickPasswordCheck(path: string, password: string): Promise<boolean | null>;
+    /**
+     * Decrypt the snapshot at `path` and check the given clients for unreadable store entries and missing records.
+     * The snapshot is not modified and the loaded stronghold, if any, is not affected.
+     * @param path
+     * @param password
+     * @param clients The clients to check, as Stronghold cannot list the clients of a snapshot.
+     * @returns
+     */
+    static verifyIntegrity(path: string, password: string, clients: ClientPath[]): Promise<IntegrityReport>;
+    /**
+     * Get the version of the plugin and of the Stronghold library it was built against.
+     * @returns
+     */
+    static version(): Promise<VersionInfo>;
+    /**
+     * Generate a random nonce with the size required by the given AEAD algorithm.
+     * @param algorithm The AEAD algorithm the nonce is used with.
+     * @returns
+     */
+    static generateNonce(algorithm: AeadAlgorithm): Promise<Uint8Array>;
+    /**
+     * Listen for strongholds locked because too many were loaded, see `Builder::with_max_loaded`.
+     * A locked stronghold must be loaded again before it can be used.
+     * @param handler Called with the snapshot path of the locked stronghold.
+     * @returns A function removing the listener.
+     */
+    static onEvicted(handler: (path: string) => void): Promise<UnlistenFn>;
+    /**
+     * Listen for failed automatic saves, see `Builder::with_commit_debounce`.
+     * The changes are kept in memory and can be saved again with {@link Stronghold.save}.
+     * @param handler Called with the snapshot path and the reason of the failure.
+     * @returns A function removing the listener.
+     */
+    static onCommitError(handler: (error: CommitError) => void): Promise<UnlistenFn>;
+    /**
+     * Run known-answer tests of the SHA-256, HMAC-SHA-256, Ed25519 and AES-256-GCM implementations, without using any snapshot.
+     * @returns
+     */
+    static cryptoSelfTest(): Promise<SelfTestReport>;
+    /**
+     * Get whether the snapshot at `path` is loaded, and if so whether it is locked and must be loaded again.
+     * @param path
+     * @returns
+     */
+    static state(path: string): Promise<SnapshotState>;
+    /**
+     * Get when the snapshot at `path` was first saved. Unlike the modification time of the file, this does not change on each save.
+     * @param path
+     * @returns
+     */
+    static createdAt(path: string): Promise<Date>;
+    /**
+     * Derive the BIP39 seed of a mnemonic without storing it.
+     * The seed is as sensitive as the mnemonic: prefer {@link ProcedureExecutor.recoverBIP39}, which keeps it in the vault,
+     * and only use this to display or verify a seed.
+     * @param mnemonic
+     * @param passphrase The optional mnemonic passphrase.
+     * @returns
+     */
+    static mnemonicToSeed(mnemonic: string, passphrase?: string): Promise<Uint8Array>;
+    /**
+     * Get the languages of the BIP39 wordlists bundled with the plugin.
+     * @returns
+     */
+    static bip39Languages(): Promise<Bip39Language[]>;
+    /**
+     * Check that a mnemonic only contains words of the wordlist and that its checksum is valid, e.g. before recovering it.
+     * @param mnemonic
+     * @param language The language of the wordlist.
+     * @returns `false` if the checksum does not match; the promise is rejected for unknown words or an invalid word count.
+     */
+    static validateMnemonic(mnemonic: string, language?: Bip39Language): Promise<boolean>;
+    /**
+     * Check whether this build of the plugin supports p2p networking.
+     * @returns
+     */
+    static p2pAvailable(): Promise<boolean>;
+    /**
+     * Get the cargo features this build of the plugin was compiled with, e.g. `kdf` or `testing`.
+     * @returns
+     */
+    static features(): Promise<string[]>;
     /**
      * Remove this instance from the cache.
      */
     unload(): Promise<void>;
     loadClient(client: ClientPath): Promise<Client>;
     createClient(client: ClientPath): Promise<Client>;
+    /**
+     * List the paths of the clients currently loaded, i.e. created or loaded since the snapshot was loaded.
+     * @returns
+     */
+    listClients(): Promise<Uint8Array[]>;
+    /**
+     * Get the number of clients currently loaded, see {@link Stronghold.listClients}.
+     * @returns
+     */
+    clientCount(): Promise<number>;
     /**
      * Persists the stronghold state to the snapshot.
      * @returns
      */
     save(): Promise<void>;
+    /**
+     * Saves the snapshot and re-encrypts it with a key derived using stronger argon2 parameters.
+     * The parameters are stored next to the snapshot and used whenever it is loaded.
+     * Only supported when the plugin uses the argon2 password hash.
+     * @param password The current password.
+     * @param params The new argon2 parameters.
+     * @returns
+     */
+    upgradeKdf(password: string, params: Argon2Config): Promise<void>;
+    /**
+     * Saves the snapshot and moves it to another path, along with the files stored next to it.
+     * Clients, stores and vaults obtained before keep using the old path and must be obtained again.
+     * @param newPath
+     * @param overwrite Whether to replace a snapshot already stored at `newPath`.
+     * @returns
+     */
+    relocate(newPath: string, overwrite?: boolean): Promise<void>;
+    /**
+     * Discards all changes made since the last save by loading the snapshot from disk again.
+     * Fails if the stronghold was never saved.
+     * @returns
+     */
+    reload(): Promise<void>;
+    /**
+     * Get a short fingerprint of the key the snapshot is encrypted with, e.g. to show it to the user for verification.
+     * The key itself cannot be recovered from the fingerprint.
+     * @returns The fingerprint as uppercase hex string.
+     */
+    keyFingerprint(): Promise<string>;
+    /**
+     * List the background tasks the plugin has scheduled for this snapshot, for diagnostics.
+     * @returns
+     */
+    activeTasks(): Promise<TaskInfo[]>;
+    /**
+     * Stop the background tasks scheduled for this snapshot.
+     * Changes whose automatic save is cancelled are kept in memory until {@link Stronghold.save} is called.
+     * @returns The number of tasks stopped.
+     */
+    cancelTasks(): Promise<number>;
+    /**
+     * Measure how long saving the snapshot takes on this device, e.g. to tune the KDF and commit debounce settings.
+     * The snapshot is written to a scratch file in the temporary directory, so unsaved changes stay unsaved.
+     * Only available when the plugin is built with the `testing` feature.
+     * @returns
+     */
+    benchmarkCommit(): Promise<Duration>;
+    /**
+     * Get non-secret structural information about the snapshot for diagnostics.
+     * Stronghold cannot list the clients of a snapshot, pass the client paths to report on.
+     * @param clients The clients to include in the report.
+     * @returns
+     */
+    debugInfo(clients?: ClientPath[]): Promise<SnapshotDebugInfo>;
 }
 export {};
@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/tauri';
+import { listen } from '@tauri-apps/api/event';
 
 function toBytesDto(v) {
     if (typeof v === "string") {
@@ -51,7 +52,7 @@ class ProcedureExecutor {
      * Derive a SLIP10 private key using a seed or key.
      * @param chain The chain path.
      * @param source The source type, either 'Seed' or 'Key'.
-     * @param sourceLocation The source location, must be the `outputLocation` of a previous call to `generateSLIP10Seed` or `deriveSLIP10`.
+     * @param sourceLocation The source location, must be the `outputLocation` of a previous call to `generateSLIP10Seed`, `recoverBIP39`, `generateBIP39` or `Vault.importSeed` for a 'Seed' source, or of `deriveSLIP10` for a 'Key' source.
      * @param outputLocation Location of the record where the private key will be stored.
      * @param hint The record hint.
      * @returns
@@ -129,6 +130,19 @@ class ProcedureExecutor {
             },
         }).then((n) => Uint8Array.from(n));
     }
+    /**
+     * Gets the Ed25519 public key of a SLIP10 private key encoded in the given format.
+     * @param privateKeyLocation The location of the private key. Must be the `outputLocation` of a previous call to `deriveSLIP10`.
+     * @param format The encoding of the returned public key.
+     * @returns A promise resolving to the public key bytes, or its hex or base64 string.
+     */
+    async exportPublicKey(privateKeyLocation, format) {
+        return await invoke("plugin:stronghold|export_public_key", {
+            ...this.procedureArgs,
+            location: privateKeyLocation,
+            format,
+        }).then((k) => (typeof k === "string" ? k : Uint8Array.from(k)));
+    }
     /**
      * Creates a Ed25519 signature from a private key.
      * @param privateKeyLocation The location of the record where the private key is stored. Must be the `outputLocation` of a previous call to `deriveSLIP10`.
@@ -147,6 +161,19 @@ class ProcedureExecutor {
             },
         }).then((n) => Uint8Array.from(n));
     }
+    /**
+     * Executes a list of procedures in order, stopping at the first failure.
+     * On failure the promise is rejected with a {@link ProcedureStepError}, and the records written
+     * by the steps before the failed one are kept.
+     * @param procedures The procedures to execute.
+     * @returns A promise resolving to the output of each procedure.
+     */
+    async executeProcedures(procedures) {
+        return await invoke("plugin:stronghold|execute_procedures", {
+            ...this.procedureArgs,
+            procedures,
+        }).then((outputs) => outputs.map((n) => Uint8Array.from(n)));
+    }
 }
 class Client {
     constructor(path, name) {
@@ -185,6 +212,18 @@ class Store {
             }
         });
     }
+    /**
+     * Get the values of several records at once.
+     * @param keys The record keys.
+     * @returns The values in the order of `keys`, `null` for records that do not exist.
+     */
+    async getMany(keys) {
+        return await invoke("plugin:stronghold|get_store_records", {
+            snapshotPath: this.path,
+            client: this.client,
+            keys: keys.map(toBytesDto),
+        }).then((values) => values.map((v) => (v != null ? Uint8Array.from(v) : null)));
+    }
     async insert(key, value, lifetime) {
         return await invoke("plugin:stronghold|save_store_record", {
             snapshotPath: this.path,
@@ -201,6 +240,48 @@ class Store {
             key: toBytesDto(key),
         }).then((v) => (v != null ? Uint8Array.from(v) : null));
     }
+    /**
+     * List the keys of all non-expired records in the store.
+     * The record values are not returned.
+     * @returns
+     */
+    async keys() {
+        return await invoke("plugin:stronghold|store_keys", {
+            snapshotPath: this.path,
+            client: this.client,
+        }).then((keys) => keys.map((k) => Uint8Array.from(k)));
+    }
+    /**
+     * Remove all records from the store, e.g. session data on sign out. The vaults of the client are not affected.
+     * @returns The number of records removed.
+     */
+    async clear() {
+        return await invoke("plugin:stronghold|store_clear", {
+            snapshotPath: this.path,
+            client: this.client,
+        });
+    }
+    /**
+     * Iterate over the keys of all non-expired records in the store, fetching them in pages.
+     * The record values are not returned.
+     * @param pageSize The number of keys fetched at once, at least 1. Each page lists and sorts all keys of the store, so prefer large pages for large stores.
+     * @returns
+     */
+    async *scan(pageSize = 100) {
+        let cursor = null;
+        do {
+            const page = await invoke("plugin:stronghold|store_scan", {
+                snapshotPath: this.path,
+                client: this.client,
+                cursor,
+                limit: pageSize,
+            });
+            for (const key of page.entries) {
+                yield Uint8Array.from(key);
+            }
+            cursor = page.nextCursor;
+        } while (cursor != null);
+    }
 }
 /**
  * A key-value storage that allows create, update and delete operations.
@@ -234,6 +315,281 @@ class Vault extends ProcedureExecutor {
             secret,
         });
     }
+    /**
+     * Generate a random symmetric key for the AEAD procedures and store it in this vault.
+     * @param location The record location.
+     * @param size The key size in bytes, currently only 32 is supported.
+     * @returns
+     */
+    async generateSecretKey(location, size = 32) {
+        return await invoke("plugin:stronghold|generate_secret_key", {
+            snapshotPath: this.path,
+            client: this.client,
+            location,
+            size,
+        });
+    }
+    /**
+     * Compute the current time-based one-time password (TOTP, RFC 6238) with an HMAC key stored in this vault.
+     * The key never leaves the vault.
+     *
+     * **Standard SHA-1 TOTP secrets are not supported**, as the vault procedures have no HMAC-SHA1.
+     * Most authenticator apps and `otpauth://` URIs without an `algorithm` parameter use SHA-1, and their codes cannot be computed here.
+     * @param secretLocation The location of the HMAC key, e.g. written with {@link Vault.insert}.
+     * @param hash The HMAC hash the secret was issued for.
+     * @param timeStep The time step in seconds.
+     * @param digits The number of digits, 6 to 8.
+     * @returns
+     */
+    async generateTotp(secretLocation, hash, timeStep = 30, digits = 6) {
+        return await invoke("plugin:stronghold|generate_totp", {
+            snapshotPath: this.path,
+            client: this.client,
+            secret: secretLocation,
+            timeStep,
+            digits,
+            hash,
+        });
+    }
+    /**
+     * Check whether a record holds the given secret, without reading the secret out of the vault.
+     * The comparison is done in constant time.
+     * @param location The record location.
+     * @param candidate The expected secret.
+     * @returns
+     */
+    async recordMatches(location, candidate) {
+        return await invoke("plugin:stronghold|record_matches", {
+            snapshotPath: this.path,
+            client: this.client,
+            location,
+            candidate: Array.from(candidate),
+        });
+    }
+    /**
+     * Compute the key check value of a symmetric key, to verify it matches an expected key without exposing it.
+     * @param location The location of the key.
+     * @param algorithm The algorithm used to encrypt the check block.
+     * @returns The first 3 bytes of the ciphertext of a zero block encrypted under the key, without the authentication tag.
+     */
+    async keyCheckValue(location, algorithm = "Aes256Gcm") {
+        return await invoke("plugin:stronghold|key_check_value", {
+            snapshotPath: this.path,
+            client: this.client,
+            location,
+            algorithm,
+        }).then((n) => Uint8Array.from(n));
+    }
+    /**
+     * List the records written or removed through the plugin since the given time, e.g. to sync only the changed records.
+     * Removed records are included.
+     * @param since
+     * @returns The locations of the records.
+     */
+    async recordsModifiedSince(since) {
+        const millis = since.getTime();
+        return await invoke("plugin:stronghold|records_modified_since", {
+            snapshotPath: this.path,
+            client: this.client,
+            since: {
+                secs_since_epoch: Math.floor(millis / 1000),
+                nanos_since_epoch: (millis % 1000) * 1e6,
+            },
+        }).then((locations) => locations.map((l) => new Location(l.type, l.payload)));
+    }
+    /**
+     * Derive the Ed25519 public keys of consecutive indices of an account, e.g. to discover used addresses up to a gap limit.
+     * The derived private keys are not kept.
+     * @param seedLocation The location of the seed.
+     * @param account The derivation chain of the account, hardened, so each index must be below 2^31.
+     * @param gapLimit The number of indices to derive, from 1 to 1000.
+     * @param start The first index. The last derived index must be below 2^31 as well.
+     * @returns
+     */
+    async scanAddresses(seedLocation, account, gapLimit, start) {
+        return await invoke("plugin:stronghold|scan_addresses", {
+            snapshotPath: this.path,
+            client: this.client,
+            seedLocation,
+            account,
+            start,
+            gapLimit,
+        }).then((addresses) => addresses.map((a) => ({ ...a, publicKey: Uint8Array.from(a.publicKey) })));
+    }
+    /**
+     * Generate an X25519 key pair for WireGuard and store its private key in this vault.
+     * The private key never leaves the vault; Stronghold can use it for X25519 key exchanges inside the vault.
+     * @param location The record location.
+     * @returns The public key encoded in base64, as expected in WireGuard configurations.
+     */
+    async generateWireguardKeypair(location) {
+        return await invoke("plugin:stronghold|generate_wireguard_keypair", {
+            snapshotPath: this.path,
+            client: this.client,
+            location,
+        });
+    }
+    /**
+     * Generate a secp256k1 private key and store it in this vault.
+     * @param location The record location.
+     * @returns
+     */
+    async generateSecp256k1(location) {
+        return await invoke("plugin:stronghold|generate_secp256k1", {
+            snapshotPath: this.path,
+            client: this.client,
+            location,
+        });
+    }
+    /**
+     * Get the compressed public key of a secp256k1 private key.
+     * @param location The location of the private key, written by {@link Vault.generateSecp256k1}.
+     * @returns
+     */
+    async secp256k1PublicKey(location) {
+        return await invoke("plugin:stronghold|secp256k1_public_key", {
+            snapshotPath: this.path,
+            client: this.client,
+            location,
+        }).then((n) => Uint8Array.from(n));
+    }
+    /**
+     * Sign the public keys of several key pairs with a signing key, e.g. to register a device with a server.
+     * All keys must have been written by the key generation APIs. The signing key must be an Ed25519 or secp256k1 key.
+     * @param signingLocation The location of the signing key.
+     * @param keyLocations The locations of the key pairs to attest.
+     * @returns
+     */
+    async attestPublicKeys(signingLocation, keyLocations) {
+        return await invoke("plugin:stronghold|attest_public_keys", {
+            snapshotPath: this.path,
+            client: this.client,
+            signingLocation,
+            keyLocations,
+        }).then((a) => ({
+            algorithm: a.algorithm,
+            signingPublicKey: Uint8Array.from(a.signingPublicKey),
+            keys: a.keys.map((k) => ({
+                location: new Location(k.location.type, k.location.payload),
+                kind: k.kind,
+                publicKey: Uint8Array.from(k.publicKey),
+            })),
+            message: Uint8Array.from(a.message),
+            signature: Uint8Array.from(a.signature),
+        }));
+    }
+    /**
+     * Sign a message with a secp256k1 private key.
+     * @param location The location of the private key, written by {@link Vault.generateSecp256k1}.
+     * @param msg The message, hashed with `hash` before signing.
+     * @param hash The message hash.
+     * @returns The 65 byte recoverable signature, with the recovery id as last byte.
+     */
+    async secp256k1Sign(location, msg, hash = "Keccak256") {
+        return await invoke("plugin:stronghold|secp256k1_sign", {
+            snapshotPath: this.path,
+            client: this.client,
+            location,
+            msg: Array.from(msg),
+            hash,
+        }).then((n) => Uint8Array.from(n));
+    }
+    /**
+     * Store a raw binary seed in this vault, to be used as the seed of {@link ProcedureExecutor.deriveSLIP10}.
+     * @param location The record location.
+     * @param seed The seed, 16 to 64 bytes long.
+     * @returns
+     */
+    async importSeed(location, seed) {
+        return await invoke("plugin:stronghold|import_seed", {
+            snapshotPath: this.path,
+            client: this.client,
+            location,
+            seed: Array.from(seed),
+        });
+    }
+    /**
+     * Store an existing private key in this vault, e.g. when migrating from another tool.
+     * The key is validated first: malformed keys are rejected.
+     * @param location The record location.
+     * @param keyBytes The 32 byte private key, or an unencrypted PKCS#8 document in DER.
+     * @param keyType The algorithm of the key.
+     * @param format The encoding of `keyBytes`.
+     * @returns
+     */
+    async importPrivateKey(location, keyBytes, keyType, format = "Raw") {
+        return await invoke("plugin:stronghold|import_private_key", {
+            snapshotPath: this.path,
+            client: this.client,
+            location,
+            keyBytes: Array.from(keyBytes),
+            keyType,
+            format,
+        });
+    }
+    /**
+     * Derive a key for the given context from a master key stored in the vault, using HKDF-SHA256.
+     * Different contexts yield independent keys, none of which leave the vault.
+     * @param masterLocation The location of the master key.
+     * @param context The purpose of the derived key, e.g. the name of the feature using it.
+     * @param outputLocation The location to store the derived key at.
+     * @returns
+     */
+    async deriveKey(masterLocation, context, outputLocation) {
+        return await invoke("plugin:stronghold|derive_key", {
+            snapshotPath: this.path,
+            client: this.client,
+            master: masterLocation,
+            context: Array.from(context),
+            output: outputLocation,
+        });
+    }
+    /**
+     * Derive a 32 byte symmetric key from a shared secret stored in the vault, e.g. the output of an X25519 key exchange,
+     * using the NIST SP 800-56A concat KDF with SHA-256. The shared secret never leaves the vault.
+     * @param sharedSecretLocation The location of the shared secret.
+     * @param algorithmId The identifier of the algorithm the derived key is used with.
+     * @param partyInfo The party information mixed into the derived key.
+     * @param outputLocation The location to store the derived key at.
+     * @returns
+     */
+    async concatKdf(sharedSecretLocation, algorithmId, partyInfo, outputLocation) {
+        return await invoke("plugin:stronghold|concat_kdf", {
+            snapshotPath: this.path,
+            client: this.client,
+            sharedSecret: sharedSecretLocation,
+            algorithmId,
+            partyInfo,
+            output: outputLocation,
+        });
+    }
+    /**
+     * Insert the contents of a file as a record of this vault.
+     * The file is read by the plugin, so its contents never pass through the webview.
+     * @param location The record location.
+     * @param filePath The path of the file to read.
+     * @returns
+     */
+    async insertFromFile(location, filePath) {
+        return await invoke("plugin:stronghold|save_record_from_file", {
+            snapshotPath: this.path,
+            client: this.client,
+            location,
+            filePath,
+        });
+    }
+    /**
+     * Get the kind of key stored in a record.
+     * @param location The record location.
+     * @returns The key kind, or `null` if the record was not written by a key generation API.
+     */
+    async recordType(location) {
+        return await invoke("plugin:stronghold|record_type", {
+            snapshotPath: this.path,
+            client: this.client,
+            location,
+        });
+    }
     /**
      * Remove a record from the vault.
      * @param location The record location.
@@ -264,15 +620,227 @@ class Stronghold {
     }
     /**
      * Load the snapshot if it exists (password must match), or start a fresh stronghold instance otherwise.
+     * Relative paths are resolved in the namespace directory if the plugin is configured with `Builder::with_namespace`, and must not contain `..` components then.
+     * @param password
+     * @param salt An optional salt for this snapshot, only supported with the argon2 password hash.
+     * It is stored next to the snapshot and used whenever it is loaded without a salt.
+     * @returns
+     */
+    static async load(path, password, salt) {
+        return await invoke("plugin:stronghold|initialize", {
+            snapshotPath: path,
+            password,
+            salt,
+        }).then(() => new Stronghold(path));
+    }
+    /**
+     * Load the snapshot if it exists, or start a fresh stronghold instance otherwise, with a key reconstructed from Shamir shares instead of a password.
+     * The shares bypass the password hash, including the pepper configured with `Builder::with_pepper_provider`.
+     * @param path
+     * @param shares At least the threshold number of shares created by {@link Stronghold.splitKey}.
+     * @param createNew Whether to ignore an existing snapshot, see {@link Stronghold.createNew}.
+     * @returns
+     */
+    static async loadWithShares(path, shares, createNew = false) {
+        return await invoke("plugin:stronghold|initialize", {
+            snapshotPath: path,
+            shares: shares.map((s) => Array.from(s)),
+            createNew,
+        }).then(() => new Stronghold(path));
+    }
+    /**
+     * Split a 32 byte snapshot key into Shamir shares, any `threshold` of which unlock the snapshot with {@link Stronghold.loadWithShares}.
+     * @param key
+     * @param threshold The number of shares needed to reconstruct the key.
+     * @param shares The number of shares to create, at most 255.
+     * @returns
+     */
+    static async splitKey(key, threshold, shares) {
+        return await invoke("plugin:stronghold|split_key", {
+            key: Array.from(key),
+            threshold,
+            shares,
+        }).then((shares) => shares.map((s) => Uint8Array.from(s)));
+    }
+    /**
+     * Reconstruct a key split by {@link Stronghold.splitKey}.
+     * Fewer shares than the threshold yield a wrong key rather than an error.
+     * @param shares
+     * @returns
+     */
+    static async combineShares(shares) {
+        return await invoke("plugin:stronghold|combine_shares", {
+            shares: shares.map((s) => Array.from(s)),
+        }).then((key) => Uint8Array.from(key));
+    }
+    /**
+     * Start a fresh, empty stronghold instance, even if a snapshot exists at `path`.
+     * The existing snapshot and the files stored next to it, e.g. its salt, are deleted, e.g. to reset a corrupted snapshot or one whose first save was interrupted.
+     * @param path
      * @param password
+     * @param salt An optional salt for this snapshot, see {@link Stronghold.load}.
      * @returns
      */
-    static async load(path, password) {
+    static async createNew(path, password, salt) {
         return await invoke("plugin:stronghold|initialize", {
             snapshotPath: path,
             password,
+            salt,
+            createNew: true,
         }).then(() => new Stronghold(path));
     }
+    /**
+     * Check the snapshot at `path` for a save that was interrupted, e.g. by a crash,
+     * and either complete it or discard the partially written file.
+     * This also happens automatically when the snapshot is loaded.
+     * If the first save of the snapshot was interrupted, or the password is wrong, this fails and the snapshot can only be started over with {@link Stronghold.createNew}.
+     * @param path
+     * @param password
+     * @returns
+     */
+    static async recover(path, password) {
+        return await invoke("plugin:stronghold|recover_snapshot", {
+            snapshotPath: path,
+            password,
+        });
+    }
+    /**
+     * Check a password against the check stored next to the snapshot at `path`, without decrypting the snapshot.
+     * This gives faster feedback than {@link Stronghold.load} on large snapshots.
+     * @param path
+     * @param password
+     * @returns Whether the password is correct, or `null` if the snapshot was saved without a check.
+     */
+    static async quickPasswordCheck(path, password) {
+        return await invoke("plugin:stronghold|quick_password_check", {
+            snapshotPath: path,
+            password,
+        });
+    }
+    /**
+     * Decrypt the snapshot at `path` and check the given clients for unreadable store entries and missing records.
+     * The snapshot is not modified and the loaded stronghold, if any, is not affected.
+     * @param path
+     * @param password
+     * @param clients The clients to check, as Stronghold cannot list the clients of a snapshot.
+     * @returns
+     */
+    static async verifyIntegrity(path, password, clients) {
+        return await invoke("plugin:stronghold|verify_integrity", {
+            snapshotPath: path,
+            password,
+            clients: clients.map(toBytesDto),
+        });
+    }
+    /**
+     * Get the version of the plugin and of the Stronghold library it was built against.
+     * @returns
+     */
+    static async version() {
+        return await invoke("plugin:stronghold|version");
+    }
+    /**
+     * Generate a random nonce with the size required by the given AEAD algorithm.
+     * @param algorithm The AEAD algorithm the nonce is used with.
+     * @returns
+     */
+    static async generateNonce(algorithm) {
+        return await invoke("plugin:stronghold|generate_nonce", {
+            algorithm,
+        }).then((n) => Uint8Array.from(n));
+    }
+    /**
+     * Listen for strongholds locked because too many were loaded, see `Builder::with_max_loaded`.
+     * A locked stronghold must be loaded again before it can be used.
+     * @param handler Called with the snapshot path of the locked stronghold.
+     * @returns A function removing the listener.
+     */
+    static async onEvicted(handler) {
+        return await listen("stronghold://evicted", (event) => handler(event.payload));
+    }
+    /**
+     * Listen for failed automatic saves, see `Builder::with_commit_debounce`.
+     * The changes are kept in memory and can be saved again with {@link Stronghold.save}.
+     * @param handler Called with the snapshot path and the reason of the failure.
+     * @returns A function removing the listener.
+     */
+    static async onCommitError(handler) {
+        return await listen("stronghold://commit-error", (event) => handler(event.payload));
+    }
+    /**
+     * Run known-answer tests of the SHA-256, HMAC-SHA-256, Ed25519 and AES-256-GCM implementations, without using any snapshot.
+     * @returns
+     */
+    static async cryptoSelfTest() {
+        return await invoke("plugin:stronghold|crypto_self_test");
+    }
+    /**
+     * Get whether the snapshot at `path` is loaded, and if so whether it is locked and must be loaded again.
+     * @param path
+     * @returns
+     */
+    static async state(path) {
+        return await invoke("plugin:stronghold|snapshot_state", {
+            snapshotPath: path,
+        });
+    }
+    /**
+     * Get when the snapshot at `path` was first saved. Unlike the modification time of the file, this does not change on each save.
+     * @param path
+     * @returns
+     */
+    static async createdAt(path) {
+        return await invoke("plugin:stronghold|snapshot_created_at", {
+            snapshotPath: path,
+        }).then((t) => new Date(t.secs_since_epoch * 1000 + t.nanos_since_epoch / 1e6));
+    }
+    /**
+     * Derive the BIP39 seed of a mnemonic without storing it.
+     * The seed is as sensitive as the mnemonic: prefer {@link ProcedureExecutor.recoverBIP39}, which keeps it in the vault,
+     * and only use this to display or verify a seed.
+     * @param mnemonic
+     * @param passphrase The optional mnemonic passphrase.
+     * @returns
+     */
+    static async mnemonicToSeed(mnemonic, passphrase) {
+        return await invoke("plugin:stronghold|mnemonic_to_seed", {
+            mnemonic,
+            passphrase,
+        }).then((n) => Uint8Array.from(n));
+    }
+    /**
+     * Get the languages of the BIP39 wordlists bundled with the plugin.
+     * @returns
+     */
+    static async bip39Languages() {
+        return await invoke("plugin:stronghold|bip39_languages");
+    }
+    /**
+     * Check that a mnemonic only contains words of the wordlist and that its checksum is valid, e.g. before recovering it.
+     * @param mnemonic
+     * @param language The language of the wordlist.
+     * @returns `false` if the checksum does not match; the promise is rejected for unknown words or an invalid word count.
+     */
+    static async validateMnemonic(mnemonic, language = "English") {
+        return await invoke("plugin:stronghold|validate_mnemonic", {
+            mnemonic,
+            language,
+        });
+    }
+    /**
+     * Check whether this build of the plugin supports p2p networking.
+     * @returns
+     */
+    static async p2pAvailable() {
+        return await invoke("plugin:stronghold|p2p_available");
+    }
+    /**
+     * Get the cargo features this build of the plugin was compiled with, e.g. `kdf` or `testing`.
+     * @returns
+     */
+    static async features() {
+        return await invoke("plugin:stronghold|features");
+    }
     /**
      * Remove this instance from the cache.
      */
@@ -293,6 +861,24 @@ class Stronghold {
             client: toBytesDto(client),
         }).then(() => new Client(this.path, client));
     }
+    /**
+     * List the paths of the clients currently loaded, i.e. created or loaded since the snapshot was loaded.
+     * @returns
+     */
+    async listClients() {
+        return await invoke("plugin:stronghold|list_clients", {
+            snapshotPath: this.path,
+        }).then((clients) => clients.map((c) => Uint8Array.from(c)));
+    }
+    /**
+     * Get the number of clients currently loaded, see {@link Stronghold.listClients}.
+     * @returns
+     */
+    async clientCount() {
+        return await invoke("plugin:stronghold|client_count", {
+            snapshotPath: this.path,
+        });
+    }
     /**
      * Persists the stronghold state to the snapshot.
      * @returns
@@ -302,6 +888,98 @@ class Stronghold {
             snapshotPath: this.path,
         });
     }
+    /**
+     * Saves the snapshot and re-encrypts it with a key derived using stronger argon2 parameters.
+     * The parameters are stored next to the snapshot and used whenever it is loaded.
+     * Only supported when the plugin uses the argon2 password hash.
+     * @param password The current password.
+     * @param params The new argon2 parameters.
+     * @returns
+     */
+    async upgradeKdf(password, params) {
+        return await invoke("plugin:stronghold|upgrade_kdf", {
+            snapshotPath: this.path,
+            password,
+            newParams: params,
+        });
+    }
+    /**
+     * Saves the snapshot and moves it to another path, along with the files stored next to it.
+     * Clients, stores and vaults obtained before keep using the old path and must be obtained again.
+     * @param newPath
+     * @param overwrite Whether to replace a snapshot already stored at `newPath`.
+     * @returns
+     */
+    async relocate(newPath, overwrite = false) {
+        await invoke("plugin:stronghold|relocate_snapshot", {
+            oldPath: this.path,
+            newPath,
+            overwrite,
+        });
+        this.path = newPath;
+    }
+    /**
+     * Discards all changes made since the last save by loading the snapshot from disk again.
+     * Fails if the stronghold was never saved.
+     * @returns
+     */
+    async reload() {
+        return await invoke("plugin:stronghold|reload", {
+            snapshotPath: this.path,
+        });
+    }
+    /**
+     * Get a short fingerprint of the key the snapshot is encrypted with, e.g. to show it to the user for verification.
+     * The key itself cannot be recovered from the fingerprint.
+     * @returns The fingerprint as uppercase hex string.
+     */
+    async keyFingerprint() {
+        return await invoke("plugin:stronghold|key_fingerprint", {
+            snapshotPath: this.path,
+        });
+    }
+    /**
+     * List the background tasks the plugin has scheduled for this snapshot, for diagnostics.
+     * @returns
+     */
+    async activeTasks() {
+        return await invoke("plugin:stronghold|active_tasks", {
+            snapshotPath: this.path,
+        });
+    }
+    /**
+     * Stop the background tasks scheduled for this snapshot.
+     * Changes whose automatic save is cancelled are kept in memory until {@link Stronghold.save} is called.
+     * @returns The number of tasks stopped.
+     */
+    async cancelTasks() {
+        return await invoke("plugin:stronghold|cancel_tasks", {
+            snapshotPath: this.path,
+        });
+    }
+    /**
+     * Measure how long saving the snapshot takes on this device, e.g. to tune the KDF and commit debounce settings.
+     * The snapshot is written to a scratch file in the temporary directory, so unsaved changes stay unsaved.
+     * Only available when the plugin is built with the `testing` feature.
+     * @returns
+     */
+    async benchmarkCommit() {
+        return await invoke("plugin:stronghold|benchmark_commit", {
+            snapshotPath: this.path,
+        });
+    }
+    /**
+     * Get non-secret structural information about the snapshot for diagnostics.
+     * Stronghold cannot list the clients of a snapshot, pass the client paths to report on.
+     * @param clients The clients to include in the report.
+     * @returns
+     */
+    async debugInfo(clients = []) {
+        return await invoke("plugin:stronghold|snapshot_debug_info", {
+            snapshotPath: this.path,
+            clients: clients.map(toBytesDto),
+        });
+    }
 }
 
 export { Client, Location, Store, Stronghold, Vault };
@@ -0,0 +1,207 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+use tauri::async_runtime::JoinHandle;
+
+use crate::{
+    pool::BlockingPool,
+    stronghold::{Error, Stronghold},
+};
+
+type ErrorHandler = dyn Fn(&Path, &Error) + Send + Sync;
+
+/// Saves strongholds automatically once they were not changed for a while,
+/// so a burst of writes results in a single commit.
+#[derive(Clone, Default)]
+pub(crate) struct AutoCommit(Option<Arc<Debounce>>);
+
+struct Debounce {
+    delay: Duration,
+    changes: AtomicU64,
+    /// The strongholds with pending changes, keyed by their snapshot path.
+    pending: Mutex<HashMap<PathBuf, Pending>>,
+    on_error: Box<ErrorHandler>,
+}
+
+struct Pending {
+    /// The number of the latest change.
+    change: u64,
+    stronghold: Arc<Stronghold>,
+    due: Instant,
+    /// The task saving the stronghold once `due`.
+    task: JoinHandle<()>,
+}
+
+impl AutoCommit {
+    /// Creates a debounced auto commit calling `on_error` when a save fails.
+    pub fn new<F: Fn(&Path, &Error) + Send + Sync + 'static>(delay: Duration, on_error: F) -> Self {
+        Self(Some(Arc::new(Debounce {
+            delay,
+            changes: AtomicU64::new(0),
+            pending: Default::default(),
+            on_error: Box::new(on_error),
+        })))
+    }
+
+    /// Records a change to `stronghold` and schedules a save after the debounce delay.
+    /// The save is skipped if another change is recorded in the meantime.
+    pub fn changed(&self, snapshot_path: PathBuf, stronghold: Arc<Stronghold>, pool: BlockingPool) {
+        let debounce = match &self.0 {
+            Some(debounce) => debounce.clone(),
+            None => return,
+        };
+        let change = debounce.changes.fetch_add(1, Ordering::Relaxed);
+        let mut pending = debounce
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let task_debounce = debounce.clone();
+        let path = snapshot_path.clone();
+        let task = tauri::async_runtime::spawn(async move {
+            let debounce = task_debounce;
+            tokio::time::sleep(debounce.delay).await;
+            let stronghold = {
+                let mut pending = debounce
+                    .pending
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                match pending.get(&path) {
+                    Some(p) if p.change == change => pending.remove(&path).map(|p| p.stronghold),
+                    _ => None,
+                }
+            };
+            if let Some(stronghold) = stronghold {
+                if let Err(e) = pool.run(move || stronghold.save()).await {
+                    log::error!("failed to save stronghold {:?}: {}", path, e);
+                    (debounce.on_error)(&path, &e);
+                }
+            }
+        });
+
+        let previous = pending.insert(
+            snapshot_path,
+            Pending {
+                change,
+                stronghold,
+                due: Instant::now() + debounce.delay,
+                task,
+            },
+        );
+        // the superseded save would be skipped anyway, stop it instead of letting it sleep
+        if let Some(previous) = previous {
+            previous.task.abort();
+        }
+    }
+
+    /// The time left until the pending save of the stronghold at `snapshot_path`, if any.
+    pub fn pending(&self, snapshot_path: &Path) -> Option<Duration> {
+        let debounce = self.0.as_ref()?;
+        let pending = debounce
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        pending
+            .get(snapshot_path)
+            .map(|p| p.due.saturating_duration_since(Instant::now()))
+    }
+
+    /// Drops the pending save of the stronghold at `snapshot_path`, e.g. because it was saved,
+    /// and stops its task. Returns whether a save was pending.
+    pub fn cancel(&self, snapshot_path: &Path) -> bool {
+        let pending = self.0.as_ref().and_then(|debounce| {
+            debounce
+                .pending
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(snapshot_path)
+        });
+        match pending {
+            Some(pending) => {
+                pending.task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Saves all strongholds with pending changes right away.
+    ///
+    /// Called on the event loop when the app exits, so failed saves are not retried.
+    pub fn flush(&self) {
+        if let Some(debounce) = &self.0 {
+            let pending = std::mem::take(
+                &mut *debounce
+                    .pending
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner),
+            );
+            for (snapshot_path, pending) in pending {
+                pending.task.abort();
+                if let Err(e) = pending.stronghold.save_once() {
+                    log::error!("failed to save stronghold {:?}: {}", snapshot_path, e);
+                    (debounce.on_error)(&snapshot_path, &e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TempSnapshot, PASSWORD};
+
+    /// Waits for aborted tasks to be dropped and returns the number of tasks still alive.
+    fn live_tasks(auto_commit: &AutoCommit) -> usize {
+        let debounce = auto_commit.0.as_ref().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            // every task holds a reference to the debounce state until it ends
+            let live = Arc::strong_count(debounce) - 1;
+            let pending = debounce.pending.lock().unwrap().len();
+            if live == pending || Instant::now() >= deadline {
+                return live;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn superseded_saves_leave_one_task() {
+        let auto_commit = AutoCommit::new(Duration::from_secs(60), |_, _| ());
+        let pool = BlockingPool::default();
+        let snapshot = TempSnapshot::new();
+        let stronghold = Arc::new(Stronghold::new(snapshot.path(), PASSWORD.to_vec()).unwrap());
+        for _ in 0..10 {
+            auto_commit.changed(snapshot.path().to_owned(), stronghold.clone(), pool.clone());
+        }
+        assert_eq!(live_tasks(&auto_commit), 1);
+        assert!(auto_commit.cancel(snapshot.path()));
+        assert_eq!(live_tasks(&auto_commit), 0);
+    }
+
+    #[test]
+    fn destroyed_strongholds_leave_no_tasks() {
+        let auto_commit = AutoCommit::new(Duration::from_secs(60), |_, _| ());
+        let pool = BlockingPool::default();
+        for _ in 0..20 {
+            let snapshot = TempSnapshot::new();
+            let stronghold = Arc::new(Stronghold::new(snapshot.path(), PASSWORD.to_vec()).unwrap());
+            auto_commit.changed(snapshot.path().to_owned(), stronghold.clone(), pool.clone());
+            assert!(auto_commit.pending(snapshot.path()).is_some());
+
+            // what `destroy` does before saving and dropping the stronghold
+            assert!(auto_commit.cancel(snapshot.path()));
+            assert!(auto_commit.pending(snapshot.path()).is_none());
+            assert_eq!(live_tasks(&auto_commit), 0);
+        }
+    }
+}
@@ -1,35 +1,290 @@
 use rand_chacha::ChaCha20Rng;
 use rand_core::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
+use crate::stronghold::{sidecar_path, Error, Result};
+
+pub use argon2::Variant;
+
 /// NOTE: Hash supplied to Stronghold must be 32bits long.
 /// This is a current limitation of Stronghold.
 const HASH_LENGTH: usize = 32;
 
+/// Cost parameters of the argon2 password hash.
+///
+/// The default matches the parameters used by [`KeyDerivation::argon2`].
+#[derive(Debug, Clone, Copy)]
+pub struct Argon2Config {
+    /// Memory usage in KiB.
+    pub mem_cost: u32,
+    /// Number of passes over the memory.
+    pub time_cost: u32,
+    /// Degree of parallelism.
+    pub lanes: u32,
+    pub variant: Variant,
+}
+
+impl Default for Argon2Config {
+    fn default() -> Self {
+        let config = argon2::Config::default();
+        Self {
+            mem_cost: config.mem_cost,
+            time_cost: config.time_cost,
+            lanes: config.lanes,
+            variant: config.variant,
+        }
+    }
+}
+
+impl Argon2Config {
+    /// Checks the parameters against the limits enforced by argon2.
+    pub fn validate(&self) -> Result<()> {
+        if self.lanes == 0 || self.lanes > 0x00FF_FFFF {
+            return Err(Error::InvalidKdfConfig(
+                "lanes must be between 1 and 16777215".into(),
+            ));
+        }
+        if self.time_cost == 0 {
+            return Err(Error::InvalidKdfConfig(
+                "time_cost must be at least 1".into(),
+            ));
+        }
+        if self.mem_cost < 8 * self.lanes {
+            return Err(Error::InvalidKdfConfig(format!(
+                "mem_cost must be at least {} for {} lanes",
+                8 * self.lanes,
+                self.lanes
+            )));
+        }
+        Ok(())
+    }
+
+    fn to_argon2(self) -> argon2::Config<'static> {
+        argon2::Config {
+            mem_cost: self.mem_cost,
+            time_cost: self.time_cost,
+            lanes: self.lanes,
+            variant: self.variant,
+            hash_length: HASH_LENGTH as u32,
+            ..Default::default()
+        }
+    }
+}
+
 pub struct KeyDerivation {}
 
 impl KeyDerivation {
     /// Will create a key from [`password`] and a generated salt.
     /// Salt will be generated to file [`salt_path`] or taken from it
     /// if file already exists
+    ///
+    /// Panics if the salt file cannot be accessed, see [`Self::try_argon2`].
     pub fn argon2(password: &str, salt_path: &Path) -> Vec<u8> {
-        let mut salt = [0u8; HASH_LENGTH];
-        create_or_get_salt(&mut salt, salt_path);
+        Self::try_argon2(password, salt_path).expect("Failed to generate hash for password")
+    }
 
-        argon2::hash_raw(password.as_bytes(), &salt, &Default::default())
+    /// Same as [`Self::argon2`], returning an error instead of panicking.
+    pub fn try_argon2(password: &str, salt_path: &Path) -> Result<Vec<u8>> {
+        Self::try_argon2_with_config(password, salt_path, &Argon2Config::default())
+    }
+
+    /// Same as [`Self::argon2`] with custom cost parameters.
+    /// The parameters must have been checked with [`Argon2Config::validate`].
+    pub fn argon2_with_config(password: &str, salt_path: &Path, config: &Argon2Config) -> Vec<u8> {
+        Self::try_argon2_with_config(password, salt_path, config)
+            .expect("Failed to generate hash for password")
+    }
+
+    /// Same as [`Self::argon2_with_config`], returning an error instead of panicking.
+    pub fn try_argon2_with_config(
+        password: &str,
+        salt_path: &Path,
+        config: &Argon2Config,
+    ) -> Result<Vec<u8>> {
+        let salt = create_or_get_salt(salt_path)?;
+        Self::try_argon2_with_salt(password, &salt, config)
+    }
+
+    /// Same as [`Self::argon2_with_config`] with the given salt instead of one read from a file.
+    /// The salt must be at least [`MIN_SALT_LENGTH`] bytes long.
+    pub fn argon2_with_salt(password: &str, salt: &[u8], config: &Argon2Config) -> Vec<u8> {
+        Self::try_argon2_with_salt(password, salt, config)
             .expect("Failed to generate hash for password")
     }
+
+    /// Same as [`Self::argon2_with_salt`], returning an error instead of panicking.
+    pub fn try_argon2_with_salt(
+        password: &str,
+        salt: &[u8],
+        config: &Argon2Config,
+    ) -> Result<Vec<u8>> {
+        argon2::hash_raw(password.as_bytes(), salt, &config.to_argon2())
+            .map_err(|e| Error::InvalidKdfConfig(e.to_string()))
+    }
+}
+
+/// Hashes `password` for the snapshot at `snapshot_path` with argon2.
+///
+/// Uses the salt stored for the snapshot (see [`snapshot_salt`]) if there is one,
+/// and the salt from the file at `salt_path` otherwise. The `namespace`, if any, is appended
+/// to the salt.
+pub fn argon2_for_snapshot(
+    password: &str,
+    snapshot_path: &Path,
+    salt_path: &Path,
+    salt: Option<Vec<u8>>,
+    namespace: Option<&str>,
+    config: &Argon2Config,
+) -> Result<Vec<u8>> {
+    let mut salt = match snapshot_salt(snapshot_path, salt)? {
+        Some(salt) => salt,
+        None => create_or_get_salt(salt_path)?,
+    };
+    if let Some(namespace) = namespace {
+        salt.extend(namespace.as_bytes());
+    }
+    KeyDerivation::try_argon2_with_salt(password, &salt, config)
+}
+
+/// The minimum salt length accepted by argon2.
+pub const MIN_SALT_LENGTH: usize = 8;
+
+/// Returns the salt stored next to the snapshot at `snapshot_path`, if any.
+///
+/// When `salt` is given for a snapshot that does not exist yet it is stored for the snapshot,
+/// replacing any salt left over from a snapshot that was never saved. For an existing snapshot
+/// it must match the stored salt, and is used without being stored if there is none, so a wrong
+/// salt passed with a wrong password does not lock the snapshot out.
+pub fn snapshot_salt(snapshot_path: &Path, salt: Option<Vec<u8>>) -> Result<Option<Vec<u8>>> {
+    let salt_path = &sidecar_path(snapshot_path, "salt");
+    let exists = snapshot_path.exists();
+
+    let stored = if salt_path.is_file() {
+        Some(std::fs::read(salt_path)?)
+    } else {
+        None
+    };
+
+    match (salt, stored) {
+        (Some(salt), _) if salt.len() < MIN_SALT_LENGTH => Err(Error::InvalidKdfConfig(format!(
+            "salt must be at least {MIN_SALT_LENGTH} bytes long"
+        ))),
+        (Some(salt), Some(stored)) if exists && salt != stored => Err(Error::SaltMismatch),
+        (Some(salt), _) if !exists => {
+            std::fs::write(salt_path, &salt).map_err(|e| Error::SnapshotWriteFailed {
+                path: salt_path.to_path_buf(),
+                reason: e.to_string(),
+            })?;
+            Ok(Some(salt))
+        }
+        (salt, stored) => Ok(salt.or(stored)),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredArgon2Config {
+    mem_cost: u32,
+    time_cost: u32,
+    lanes: u32,
+    variant: u32,
+}
+
+/// The sidecar holding the argon2 parameters of a snapshot.
+const CONFIG_EXTENSION: &str = "kdf";
+
+/// The sidecar holding the parameters of an upgrade, until the snapshot is known to be saved
+/// with them, see [`stage_snapshot_config`].
+const STAGED_CONFIG_EXTENSION: &str = "kdf.new";
+
+/// Returns the argon2 parameters stored for the snapshot at `snapshot_path`, if any.
+pub fn snapshot_config(snapshot_path: &Path) -> Result<Option<Argon2Config>> {
+    read_config(&sidecar_path(snapshot_path, CONFIG_EXTENSION))
+}
+
+/// Returns the parameters staged by [`stage_snapshot_config`] for the snapshot at
+/// `snapshot_path`, if an upgrade was interrupted.
+pub fn staged_snapshot_config(snapshot_path: &Path) -> Result<Option<Argon2Config>> {
+    read_config(&sidecar_path(snapshot_path, STAGED_CONFIG_EXTENSION))
+}
+
+fn read_config(config_path: &Path) -> Result<Option<Argon2Config>> {
+    if !config_path.is_file() {
+        return Ok(None);
+    }
+    let stored: StoredArgon2Config = serde_json::from_slice(&std::fs::read(config_path)?)
+        .map_err(|e| Error::InvalidKdfConfig(e.to_string()))?;
+    let config = Argon2Config {
+        mem_cost: stored.mem_cost,
+        time_cost: stored.time_cost,
+        lanes: stored.lanes,
+        variant: Variant::from_u32(stored.variant)
+            .map_err(|e| Error::InvalidKdfConfig(e.to_string()))?,
+    };
+    // the file may have been damaged or tampered with
+    config.validate()?;
+    Ok(Some(config))
+}
+
+/// Stores the argon2 parameters the snapshot at `snapshot_path` is about to be saved with,
+/// next to the current ones.
+///
+/// Once the snapshot is saved they replace the current ones with [`commit_snapshot_config`].
+/// If that does not happen, e.g. because the app crashed, the snapshot may be saved with either
+/// parameters, so the next load tries both and keeps the ones that unlock it.
+pub fn stage_snapshot_config(snapshot_path: &Path, config: &Argon2Config) -> Result<()> {
+    let config_path = sidecar_path(snapshot_path, STAGED_CONFIG_EXTENSION);
+    let stored = StoredArgon2Config {
+        mem_cost: config.mem_cost,
+        time_cost: config.time_cost,
+        lanes: config.lanes,
+        variant: config.variant.as_u32(),
+    };
+    let json = serde_json::to_vec(&stored).map_err(|e| Error::InvalidKdfConfig(e.to_string()))?;
+    std::fs::write(&config_path, json).map_err(|e| Error::SnapshotWriteFailed {
+        path: config_path,
+        reason: e.to_string(),
+    })
+}
+
+/// Makes the parameters staged by [`stage_snapshot_config`] the ones used whenever the snapshot
+/// at `snapshot_path` is loaded, replacing the current ones atomically.
+pub fn commit_snapshot_config(snapshot_path: &Path) -> Result<()> {
+    let config_path = sidecar_path(snapshot_path, CONFIG_EXTENSION);
+    std::fs::rename(
+        sidecar_path(snapshot_path, STAGED_CONFIG_EXTENSION),
+        &config_path,
+    )
+    .map_err(|e| Error::SnapshotWriteFailed {
+        path: config_path,
+        reason: e.to_string(),
+    })
+}
+
+/// Discards the parameters staged by [`stage_snapshot_config`], keeping the current ones.
+pub fn discard_staged_snapshot_config(snapshot_path: &Path) -> Result<()> {
+    let staged_path = sidecar_path(snapshot_path, STAGED_CONFIG_EXTENSION);
+    std::fs::remove_file(&staged_path).map_err(|e| Error::SnapshotWriteFailed {
+        path: staged_path,
+        reason: e.to_string(),
+    })
 }
 
-fn create_or_get_salt(salt: &mut [u8], salt_path: &Path) {
+fn create_or_get_salt(salt_path: &Path) -> Result<Vec<u8>> {
+    let access_failed = |e: std::io::Error| Error::FileAccess {
+        path: salt_path.to_path_buf(),
+        reason: e.to_string(),
+    };
     if salt_path.is_file() {
         // Get existing salt
-        let tmp = std::fs::read(salt_path).unwrap();
-        salt.clone_from_slice(&tmp);
+        std::fs::read(salt_path).map_err(access_failed)
     } else {
         // Generate new salt
+        let mut salt = vec![0u8; HASH_LENGTH];
         let mut gen = ChaCha20Rng::from_entropy();
-        gen.fill_bytes(salt);
-        std::fs::write(salt_path, salt).expect("Failed to write salt for Stronghold")
+        gen.fill_bytes(&mut salt);
+        std::fs::write(salt_path, &salt).map_err(access_failed)?;
+        Ok(salt)
     }
 }
@@ -1,40 +1,181 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     fmt,
-    path::PathBuf,
-    sync::{Arc, Mutex},
-    time::Duration,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant, SystemTime},
 };
 
+use base64::{engine::general_purpose::STANDARD, Engine};
+use crypto::keys::bip39;
 use iota_stronghold::{
     procedures::{
-        BIP39Generate, BIP39Recover, Chain, Ed25519Sign, KeyType as StrongholdKeyType,
-        MnemonicLanguage, PublicKey, Slip10Derive, Slip10DeriveInput, Slip10Generate,
-        StrongholdProcedure,
+        AeadCipher, AeadEncrypt, BIP39Generate, BIP39Recover, Chain, ConcatKdf, Ed25519Sign,
+        GenerateKey, Hkdf, Hmac, KeyType as StrongholdKeyType, MnemonicLanguage, PublicKey,
+        Secp256k1EcdsaFlavor, Secp256k1EcdsaSign, Sha2Hash, Slip10Derive, Slip10DeriveInput,
+        Slip10Generate, StrongholdProcedure,
     },
     Client, Location,
 };
-use serde::{de::Visitor, Deserialize, Deserializer};
-use stronghold::{Error, Result, Stronghold};
+use serde::{de::Visitor, Deserialize, Deserializer, Serialize};
+use stronghold::{Error, HashAlgorithm, Result, SnapshotRecovery, Stronghold};
 use tauri::{
     plugin::{Builder as PluginBuilder, TauriPlugin},
-    Manager, Runtime, State,
+    AppHandle, Manager, RunEvent, Runtime, State,
 };
 use zeroize::Zeroize;
 
 #[cfg(feature = "kdf")]
 pub mod kdf;
 
+mod commit;
+mod pkcs8;
+mod pool;
+mod self_test;
+mod shamir;
+mod store;
 pub mod stronghold;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+use commit::AutoCommit;
+use pool::BlockingPool;
+use store::StoreEncryption;
+
+/// The `iota_stronghold` version this crate is built against, keep in sync with `Cargo.toml`.
+const STRONGHOLD_VERSION: &str = "1";
+
+/// Key sizes accepted by the AEAD procedures (AES-256-GCM and XChaCha20-Poly1305).
+const AEAD_KEY_SIZES: &[usize] = &[32];
+
+/// The number of bytes of a key check value, see [`key_check_value`].
+const KCV_LENGTH: usize = 3;
+
+/// The size of the authentication tag of the AEAD procedures, see [`split_aead_output`].
+const AEAD_TAG_SIZE: usize = 16;
+
+/// The number of digits a one-time password may have, as per RFC 4226.
+const OTP_DIGITS: std::ops::RangeInclusive<u32> = 6..=8;
+
+/// Seed lengths accepted for SLIP10 master seeds, from 128 to 512 bits as per BIP32.
+const SEED_LENGTHS: std::ops::RangeInclusive<usize> = 16..=64;
 
 type PasswordHashFn = dyn Fn(&str) -> Vec<u8> + Send + Sync;
 
+type PepperFn = dyn Fn() -> Vec<u8> + Send + Sync;
+
+/// How long commands wait for a snapshot that is still being loaded by default,
+/// see [`Builder::with_busy_timeout`].
+const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How often commands check whether a snapshot they wait for finished loading.
+const BUSY_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// The strongholds loaded by the plugin, keyed by their snapshot path.
 #[derive(Default)]
-struct StrongholdCollection(Arc<Mutex<HashMap<PathBuf, Stronghold>>>);
+pub struct StrongholdCollection(Arc<Mutex<HashMap<PathBuf, Arc<Stronghold>>>>, Arc<Loading>);
+
+/// The snapshots being loaded by [`initialize`], so commands using them can wait for them
+/// instead of failing with [`Error::StrongholdNotInitialized`].
+struct Loading {
+    paths: Mutex<HashSet<PathBuf>>,
+    timeout: Duration,
+}
+
+impl Default for Loading {
+    fn default() -> Self {
+        Self {
+            paths: Default::default(),
+            timeout: DEFAULT_BUSY_TIMEOUT,
+        }
+    }
+}
+
+impl Loading {
+    /// Marks the snapshot at `snapshot_path` as loading until the returned guard is dropped.
+    fn start(&self, snapshot_path: PathBuf) -> LoadingGuard<'_> {
+        self.paths
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(snapshot_path.clone());
+        LoadingGuard {
+            loading: self,
+            snapshot_path,
+        }
+    }
+
+    fn contains(&self, snapshot_path: &Path) -> bool {
+        self.paths
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(snapshot_path)
+    }
+}
+
+struct LoadingGuard<'a> {
+    loading: &'a Loading,
+    snapshot_path: PathBuf,
+}
+
+impl Drop for LoadingGuard<'_> {
+    fn drop(&mut self) {
+        self.loading
+            .paths
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.snapshot_path);
+    }
+}
+
+struct PasswordHashFunction(PasswordHasher);
+
+struct DefaultClient(Option<Vec<u8>>);
+
+struct FileMode(u32);
 
-struct PasswordHashFunction(Box<PasswordHashFn>);
+struct CommitRetries(u32);
 
-#[derive(Deserialize, Hash, Eq, PartialEq, Ord, PartialOrd)]
+struct MaxLoaded(Option<usize>);
+
+struct FingerprintHash(HashAlgorithm);
+
+/// The directory relative snapshot paths are resolved in, see [`Builder::with_namespace`].
+struct Namespace(Option<String>);
+
+impl Namespace {
+    /// Resolves `snapshot_path` in the namespace directory if it is relative, rejecting relative
+    /// paths with `..` components, which could leave it.
+    fn resolve(&self, snapshot_path: PathBuf) -> Result<PathBuf> {
+        match &self.0 {
+            Some(namespace) if snapshot_path.is_relative() => {
+                if snapshot_path
+                    .components()
+                    .any(|c| matches!(c, std::path::Component::ParentDir))
+                {
+                    return Err(Error::InvalidSnapshotPath(snapshot_path));
+                }
+                Ok(Path::new(namespace).join(snapshot_path))
+            }
+            _ => Ok(snapshot_path),
+        }
+    }
+}
+
+/// The event emitted with the snapshot path when a stronghold is locked by [`Builder::with_max_loaded`].
+const EVICTED_EVENT: &str = "stronghold://evicted";
+
+/// The event emitted with a [`CommitErrorPayload`] when a save scheduled by
+/// [`Builder::with_commit_debounce`] fails.
+const COMMIT_ERROR_EVENT: &str = "stronghold://commit-error";
+
+#[derive(Clone, Serialize)]
+#[cfg_attr(not(feature = "snake-case"), serde(rename_all = "camelCase"))]
+struct CommitErrorPayload {
+    snapshot_path: PathBuf,
+    reason: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Hash, Eq, PartialEq, Ord, PartialOrd)]
 #[serde(untagged)]
 enum BytesDto {
     Text(String),
@@ -59,7 +200,7 @@ impl From<BytesDto> for Vec<u8> {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "payload")]
 enum LocationDto {
     Generic { vault: BytesDto, record: BytesDto },
@@ -75,11 +216,140 @@ impl From<LocationDto> for Location {
     }
 }
 
+impl LocationDto {
+    fn record_type_key(&self) -> Vec<u8> {
+        match self {
+            Self::Generic { vault, record } => record_type_key(vault.as_ref(), record.as_ref()),
+            Self::Counter { vault, counter } => counter_record_type_key(vault.as_ref(), *counter),
+        }
+    }
+}
+
+/// The vault holding the keys derived by [`scan_addresses`] while their public keys are computed.
+const SCAN_VAULT: &[u8] = b"tauri-plugin-stronghold:scan";
+
+/// The maximum number of keys derived by a single [`scan_addresses`] call.
+const MAX_GAP_LIMIT: u32 = 1000;
+
+/// The first hardened index, the indices of a chain are hardened so they must be below it.
+const HARDENED_INDEX: u32 = 1 << 31;
+
+/// Domain separation for the message signed by [`attest_public_keys`].
+const ATTESTATION_CONTEXT: &[u8] = b"tauri-plugin-stronghold:attestation:v1";
+
+/// Prefix of the store keys used to remember the kind of key held by a vault record.
+const RECORD_TYPE_PREFIX: &[u8] = b"tauri-plugin-stronghold:record-type:";
+
+/// Prefix of the store keys used to remember when a vault record was last written or removed,
+/// followed by the same record name as [`record_type_key`].
+const RECORD_MODIFIED_PREFIX: &[u8] = b"tauri-plugin-stronghold:record-modified:";
+
+/// Whether `key` is one of the store entries kept by the plugin about vault records and
+/// encrypted store values, which are hidden from the store commands.
+fn is_record_metadata(key: &[u8]) -> bool {
+    key.starts_with(RECORD_TYPE_PREFIX)
+        || key.starts_with(RECORD_MODIFIED_PREFIX)
+        || key.starts_with(store::ENCRYPTED_MARKER_PREFIX)
+}
+
+/// The inverse of [`record_type_key`].
+fn record_type_location(key: &[u8]) -> Option<Location> {
+    record_location(key.strip_prefix(RECORD_TYPE_PREFIX)?).map(Location::from)
+}
+
+/// Parses the record name of a [`record_type_key`] or [`counter_record_type_key`], without its
+/// prefix.
+fn record_location(name: &[u8]) -> Option<LocationDto> {
+    let name = std::str::from_utf8(name).ok()?;
+    let (vault, record) = name.split_once('/')?;
+    let vault = BytesDto::Raw(hex::decode(vault).ok()?);
+    match record.strip_prefix('#') {
+        Some(counter) => Some(LocationDto::Counter {
+            vault,
+            counter: counter.parse().ok()?,
+        }),
+        None => Some(LocationDto::Generic {
+            vault,
+            record: BytesDto::Raw(hex::decode(record).ok()?),
+        }),
+    }
+}
+
+/// The store key of the kind of the generic record `record` of `vault`.
+fn record_type_key(vault: &[u8], record: &[u8]) -> Vec<u8> {
+    record_type_key_for(vault, &hex::encode(record))
+}
+
+/// The store key of the kind of the counter record `counter` of `vault`.
+fn counter_record_type_key(vault: &[u8], counter: usize) -> Vec<u8> {
+    // hex encoded record paths never contain `#`, so this cannot collide
+    record_type_key_for(vault, &format!("#{counter}"))
+}
+
+fn record_type_key_for(vault: &[u8], name: &str) -> Vec<u8> {
+    let mut key = RECORD_TYPE_PREFIX.to_vec();
+    key.extend(hex::encode(vault).bytes());
+    key.push(b'/');
+    key.extend(name.bytes());
+    key
+}
+
+/// The kind of key held by a vault record written by one of the plugin's key generation commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+enum KeyKind {
+    Seed,
+    Ed25519,
+    X25519,
+    Symmetric,
+    Secp256k1,
+}
+
+impl KeyKind {
+    fn tag(self) -> u8 {
+        self as u8
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        [
+            Self::Seed,
+            Self::Ed25519,
+            Self::X25519,
+            Self::Symmetric,
+            Self::Secp256k1,
+        ]
+        .into_iter()
+        .find(|kind| kind.tag() == tag)
+    }
+}
+
+/// Remembers the kind of key stored in the record identified by `key`, see [`record_type_key`],
+/// and that the record was modified now.
+fn set_record_type(client: &Client, key: Vec<u8>, kind: Option<KeyKind>) -> Result<()> {
+    let mut modified_key = RECORD_MODIFIED_PREFIX.to_vec();
+    modified_key.extend(&key[RECORD_TYPE_PREFIX.len()..]);
+    let now = SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    client.store().insert(
+        modified_key,
+        (now.as_millis() as u64).to_be_bytes().to_vec(),
+        None,
+    )?;
+
+    match kind {
+        Some(kind) => client.store().insert(key, vec![kind.tag()], None)?,
+        None => client.store().delete(&key)?,
+    };
+    Ok(())
+}
+
 #[derive(Deserialize)]
 #[serde(tag = "type", content = "payload")]
 #[allow(clippy::upper_case_acronyms)]
 enum Slip10DeriveInputDto {
+    /// A seed stored by the `SLIP10Generate`, `BIP39Generate` or `BIP39Recover` procedures.
     Seed(LocationDto),
+    /// A key stored by a previous `SLIP10Derive` procedure.
     Key(LocationDto),
 }
 
@@ -136,13 +406,141 @@ impl<'de> Deserialize<'de> for KeyType {
     }
 }
 
+#[derive(Serialize)]
+struct VersionInfo {
+    plugin: &'static str,
+    stronghold: &'static str,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(not(feature = "snake-case"), serde(rename_all = "camelCase"))]
+#[cfg_attr(not(feature = "kdf"), allow(dead_code))]
+struct Argon2ConfigDto {
+    mem_cost: u32,
+    time_cost: u32,
+    lanes: u32,
+    variant: Argon2VariantDto,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(not(feature = "kdf"), allow(dead_code))]
+enum Argon2VariantDto {
+    Argon2d,
+    Argon2i,
+    Argon2id,
+}
+
+#[cfg(feature = "kdf")]
+impl From<Argon2ConfigDto> for kdf::Argon2Config {
+    fn from(dto: Argon2ConfigDto) -> kdf::Argon2Config {
+        kdf::Argon2Config {
+            mem_cost: dto.mem_cost,
+            time_cost: dto.time_cost,
+            lanes: dto.lanes,
+            variant: match dto.variant {
+                Argon2VariantDto::Argon2d => kdf::Variant::Argon2d,
+                Argon2VariantDto::Argon2i => kdf::Variant::Argon2i,
+                Argon2VariantDto::Argon2id => kdf::Variant::Argon2id,
+            },
+        }
+    }
+}
+
+/// The party information of the NIST SP 800-56A concat KDF, see [`concat_kdf`].
+#[derive(Deserialize, Default)]
+#[serde(default)]
+#[cfg_attr(not(feature = "snake-case"), serde(rename_all = "camelCase"))]
+struct PartyInfoDto {
+    party_u: Vec<u8>,
+    party_v: Vec<u8>,
+    public: Vec<u8>,
+    private: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PublicKeyFormat {
+    Raw,
+    Hex,
+    Base64,
+}
+
+/// The hash applied to the message before signing it with secp256k1.
+#[derive(Deserialize)]
+enum Secp256k1HashDto {
+    /// As used by Ethereum.
+    Keccak256,
+    /// As used by Bitcoin.
+    Sha256,
+}
+
+impl From<Secp256k1HashDto> for Secp256k1EcdsaFlavor {
+    fn from(dto: Secp256k1HashDto) -> Secp256k1EcdsaFlavor {
+        match dto {
+            Secp256k1HashDto::Keccak256 => Secp256k1EcdsaFlavor::Keccak256,
+            Secp256k1HashDto::Sha256 => Secp256k1EcdsaFlavor::Sha256,
+        }
+    }
+}
+
+/// The HMAC hash of a TOTP, see [`generate_totp`].
+#[derive(Deserialize)]
+enum TotpHashDto {
+    /// Not supported by the vault procedures, but accepted to return a clear error.
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// The BIP39 wordlists bundled with the plugin.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+enum Bip39Language {
+    English,
+    Japanese,
+}
+
+impl Bip39Language {
+    const ALL: [Self; 2] = [Self::English, Self::Japanese];
+
+    fn wordlist(self) -> &'static bip39::wordlist::Wordlist<'static> {
+        match self {
+            Self::English => &bip39::wordlist::ENGLISH,
+            Self::Japanese => &bip39::wordlist::JAPANESE,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+enum AeadAlgorithm {
+    Aes256Gcm,
+    XChaCha20Poly1305,
+}
+
+impl AeadAlgorithm {
+    fn nonce_size(&self) -> usize {
+        match self {
+            Self::Aes256Gcm => 12,
+            Self::XChaCha20Poly1305 => 24,
+        }
+    }
+}
+
+impl From<AeadAlgorithm> for AeadCipher {
+    fn from(algorithm: AeadAlgorithm) -> AeadCipher {
+        match algorithm {
+            AeadAlgorithm::Aes256Gcm => AeadCipher::Aes256Gcm,
+            AeadAlgorithm::XChaCha20Poly1305 => AeadCipher::XChaCha20Poly1305,
+        }
+    }
+}
+
 #[derive(Deserialize)]
 #[serde(tag = "type", content = "payload")]
 #[allow(clippy::upper_case_acronyms)]
 enum ProcedureDto {
     SLIP10Generate {
         output: LocationDto,
-        #[serde(rename = "sizeBytes")]
+        #[cfg_attr(not(feature = "snake-case"), serde(rename = "sizeBytes"))]
         size_bytes: Option<usize>,
     },
     SLIP10Derive {
@@ -162,16 +560,40 @@ enum ProcedureDto {
     PublicKey {
         #[serde(rename = "type")]
         ty: KeyType,
-        #[serde(rename = "privateKey")]
+        #[cfg_attr(not(feature = "snake-case"), serde(rename = "privateKey"))]
         private_key: LocationDto,
     },
     Ed25519Sign {
-        #[serde(rename = "privateKey")]
+        #[cfg_attr(not(feature = "snake-case"), serde(rename = "privateKey"))]
         private_key: LocationDto,
         msg: String,
     },
 }
 
+impl ProcedureDto {
+    /// The record written by the procedure and the kind of key stored in it.
+    fn output(&self) -> Option<(&LocationDto, KeyKind)> {
+        match self {
+            Self::SLIP10Generate { output, .. }
+            | Self::BIP39Recover { output, .. }
+            | Self::BIP39Generate { output, .. } => Some((output, KeyKind::Seed)),
+            Self::SLIP10Derive { output, .. } => Some((output, KeyKind::Ed25519)),
+            Self::PublicKey { .. } | Self::Ed25519Sign { .. } => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::SLIP10Generate { .. } => "SLIP10Generate",
+            Self::SLIP10Derive { .. } => "SLIP10Derive",
+            Self::BIP39Recover { .. } => "BIP39Recover",
+            Self::BIP39Generate { .. } => "BIP39Generate",
+            Self::PublicKey { .. } => "PublicKey",
+            Self::Ed25519Sign { .. } => "Ed25519Sign",
+        }
+    }
+}
+
 impl From<ProcedureDto> for StrongholdProcedure {
     fn from(dto: ProcedureDto) -> StrongholdProcedure {
         match dto {
@@ -223,196 +645,2171 @@ impl From<ProcedureDto> for StrongholdProcedure {
 }
 
 #[tauri::command]
-async fn initialize(
+fn version() -> VersionInfo {
+    VersionInfo {
+        plugin: env!("CARGO_PKG_VERSION"),
+        stronghold: STRONGHOLD_VERSION,
+    }
+}
+
+/// Whether the p2p commands are available. The plugin does not support p2p networking yet,
+/// this allows the frontend to check for it without relying on the command list.
+#[tauri::command]
+fn p2p_available() -> bool {
+    false
+}
+
+/// The cargo features the plugin was built with, so the frontend can adapt to the build with
+/// a single query. `p2p` will be listed once p2p networking is supported, see [`p2p_available`].
+#[tauri::command]
+fn features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "kdf") {
+        features.push("kdf");
+    }
+    if cfg!(feature = "testing") {
+        features.push("testing");
+    }
+    if cfg!(feature = "snake-case") {
+        features.push("snake-case");
+    }
+    features
+}
+
+/// Structural, non-secret information about a snapshot, see [`snapshot_debug_info`].
+#[derive(Serialize)]
+#[cfg_attr(not(feature = "snake-case"), serde(rename_all = "camelCase"))]
+struct SnapshotDebugInfo {
+    /// The major version of `iota_stronghold` the plugin is built against, which determines the
+    /// snapshot format.
+    stronghold_version: &'static str,
+    plugin_version: &'static str,
+    snapshot_exists: bool,
+    snapshot_size: Option<u64>,
+    initialized: bool,
+    /// The argon2 parameters stored for the snapshot, if any.
+    kdf: Option<KdfDebugInfo>,
+    clients: BTreeMap<String, ClientDebugInfo>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(feature = "snake-case"), serde(rename_all = "camelCase"))]
+#[cfg_attr(not(feature = "kdf"), allow(dead_code))]
+struct KdfDebugInfo {
+    mem_cost: u32,
+    time_cost: u32,
+    lanes: u32,
+    variant: String,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(feature = "snake-case"), serde(rename_all = "camelCase"))]
+struct ClientDebugInfo {
+    loaded: bool,
+    /// The number of store entries, without the plugin's record metadata. `None` if not loaded.
+    store_keys: Option<usize>,
+    /// The number of records written by the plugin's key generation commands.
+    typed_records: Option<usize>,
+}
+
+/// Structural, non-secret information about a snapshot for diagnostics.
+///
+/// Stronghold does not expose the clients and records contained in a snapshot, so client
+/// details are only reported for the `clients` given by the caller: the number of store
+/// entries and the number of records written by the plugin's key generation commands.
+#[tauri::command]
+async fn snapshot_debug_info(
     collection: State<'_, StrongholdCollection>,
-    hash_function: State<'_, PasswordHashFunction>,
+    namespace: State<'_, Namespace>,
     snapshot_path: PathBuf,
-    mut password: String,
-) -> Result<()> {
-    let hash = (hash_function.0)(&password);
-    password.zeroize();
-    let stronghold = Stronghold::new(snapshot_path.clone(), hash)?;
+    clients: Option<Vec<BytesDto>>,
+) -> Result<SnapshotDebugInfo> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    // not touched, so diagnostics do not keep it from being evicted
+    let stronghold = match get_loaded(&collection, snapshot_path.clone()).await {
+        Ok(stronghold) => Some(stronghold),
+        Err(Error::StrongholdNotInitialized | Error::Locked(_)) => None,
+        Err(e) => return Err(e),
+    };
 
-    collection
-        .0
-        .lock()
-        .unwrap()
-        .insert(snapshot_path, stronghold);
+    let mut client_info = BTreeMap::new();
+    if let Some(stronghold) = &stronghold {
+        let _reading = stronghold.read_guard();
+        for client_path in clients.unwrap_or_default() {
+            let name = match &client_path {
+                BytesDto::Text(t) => t.clone(),
+                BytesDto::Raw(b) => hex::encode(b),
+            };
+            let info = match stronghold.get_client(&client_path) {
+                Ok(client) => {
+                    let keys = client.store().keys()?;
+                    let records = keys
+                        .iter()
+                        .filter(|k| k.starts_with(RECORD_TYPE_PREFIX))
+                        .count();
+                    let metadata = keys.iter().filter(|k| is_record_metadata(k)).count();
+                    ClientDebugInfo {
+                        loaded: true,
+                        store_keys: Some(keys.len() - metadata),
+                        typed_records: Some(records),
+                    }
+                }
+                Err(_) => ClientDebugInfo {
+                    loaded: false,
+                    store_keys: None,
+                    typed_records: None,
+                },
+            };
+            client_info.insert(name, info);
+        }
+    }
 
-    Ok(())
+    #[cfg(feature = "kdf")]
+    let kdf = kdf::snapshot_config(&snapshot_path)?.map(|config| KdfDebugInfo {
+        mem_cost: config.mem_cost,
+        time_cost: config.time_cost,
+        lanes: config.lanes,
+        variant: format!("{:?}", config.variant),
+    });
+    #[cfg(not(feature = "kdf"))]
+    let kdf = None;
+
+    Ok(SnapshotDebugInfo {
+        stronghold_version: STRONGHOLD_VERSION,
+        plugin_version: env!("CARGO_PKG_VERSION"),
+        snapshot_exists: snapshot_path.is_file(),
+        snapshot_size: std::fs::metadata(&snapshot_path).map(|m| m.len()).ok(),
+        initialized: stronghold.is_some(),
+        kdf,
+        clients: client_info,
+    })
 }
 
 #[tauri::command]
-async fn destroy(
+async fn initialize<R: Runtime>(
+    app: AppHandle<R>,
     collection: State<'_, StrongholdCollection>,
+    hash_function: State<'_, PasswordHashFunction>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    file_mode: State<'_, FileMode>,
+    commit_retries: State<'_, CommitRetries>,
+    max_loaded: State<'_, MaxLoaded>,
+    namespace: State<'_, Namespace>,
     snapshot_path: PathBuf,
+    mut password: Option<String>,
+    salt: Option<Vec<u8>>,
+    create_new: Option<bool>,
+    shares: Option<Vec<Vec<u8>>>,
 ) -> Result<()> {
-    let mut collection = collection.0.lock().unwrap();
-    if let Some(stronghold) = collection.remove(&snapshot_path) {
-        if let Err(e) = stronghold.save() {
-            collection.insert(snapshot_path, stronghold);
-            return Err(e);
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    if shares.is_some() && password.is_some() {
+        return Err(Error::InvalidShares(
+            "a password cannot be used with key shares".into(),
+        ));
+    }
+    if shares.is_some() && salt.is_some() {
+        return Err(Error::InvalidShares(
+            "a salt cannot be used with key shares".into(),
+        ));
+    }
+    if namespace.0.is_some() {
+        if let Some(dir) = snapshot_path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| Error::FileAccess {
+                path: dir.to_path_buf(),
+                reason: e.to_string(),
+            })?;
         }
     }
+    let hash_function = hash_function.0.clone();
+    let path = snapshot_path.clone();
+    // keep it marked as loading until it is in the collection, see `get_loaded`
+    let _loading = collection.1.start(snapshot_path.clone());
+    let stronghold = pool
+        .run(move || {
+            let stronghold = open_stronghold(
+                &hash_function,
+                path,
+                password.as_deref(),
+                salt,
+                create_new.unwrap_or_default(),
+                shares,
+            );
+            password.zeroize();
+            stronghold
+        })
+        .await?
+        .with_file_mode(file_mode.0)
+        .with_commit_retries(commit_retries.0);
+
+    collection
+        .0
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(snapshot_path.clone(), Arc::new(stronghold));
+
+    if let Some(max_loaded) = max_loaded.0 {
+        evict(
+            &app,
+            &collection,
+            &auto_commit,
+            &pool,
+            &snapshot_path,
+            max_loaded,
+        )
+        .await;
+    }
+
     Ok(())
 }
 
-#[tauri::command]
-async fn save(collection: State<'_, StrongholdCollection>, snapshot_path: PathBuf) -> Result<()> {
-    let collection = collection.0.lock().unwrap();
-    if let Some(stronghold) = collection.get(&snapshot_path) {
-        stronghold.save()?;
+/// Loads the stronghold at `path` with the key derived from `password` or reconstructed from
+/// `shares`, see [`initialize`].
+///
+/// With `create_new` the snapshot and the files stored next to it are deleted first, so the new
+/// snapshot does not inherit e.g. the salt or the KDF parameters of the old one.
+fn open_stronghold(
+    hash_function: &PasswordHasher,
+    path: PathBuf,
+    password: Option<&str>,
+    salt: Option<Vec<u8>>,
+    create_new: bool,
+    shares: Option<Vec<Vec<u8>>>,
+) -> Result<Stronghold> {
+    if shares.is_none() && password.is_none() {
+        return Err(Error::PasswordRequired);
+    }
+    if create_new {
+        Stronghold::remove_snapshot(&path)?;
+    }
+    // shares reconstruct the snapshot key itself, without the password hash and pepper
+    let hash = match (shares.map(zeroize::Zeroizing::new), password) {
+        (Some(shares), _) => shamir::combine(&shares)?,
+        (None, Some(password)) => hash_function.hash_for_load(password, &path, salt)?,
+        (None, None) => return Err(Error::PasswordRequired),
+    };
+    if create_new {
+        Stronghold::create_new(path, hash)
+    } else {
+        Stronghold::new(path, hash)
     }
-    Ok(())
 }
 
+/// Splits a snapshot key into `shares` Shamir shares, any `threshold` of which unlock the
+/// snapshot when passed to [`initialize`] instead of a password, e.g. to require several devices
+/// or parties to unlock it.
 #[tauri::command]
-async fn create_client(
-    collection: State<'_, StrongholdCollection>,
-    snapshot_path: PathBuf,
-    client: BytesDto,
-) -> Result<()> {
-    let stronghold = get_stronghold(collection, snapshot_path)?;
-    stronghold.create_client(client)?;
-    Ok(())
+async fn split_key(key: Vec<u8>, threshold: u8, shares: u8) -> Result<Vec<Vec<u8>>> {
+    let key = zeroize::Zeroizing::new(key);
+    shamir::split(&key, threshold, shares)
 }
 
+/// Reconstructs the key split by [`split_key`] from at least the threshold number of shares.
 #[tauri::command]
-async fn load_client(
-    collection: State<'_, StrongholdCollection>,
-    snapshot_path: PathBuf,
-    client: BytesDto,
-) -> Result<()> {
-    let stronghold = get_stronghold(collection, snapshot_path)?;
-    stronghold.load_client(client)?;
-    Ok(())
+async fn combine_shares(shares: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    let shares = zeroize::Zeroizing::new(shares);
+    shamir::combine(&shares)
 }
 
-#[tauri::command]
-async fn get_store_record(
-    collection: State<'_, StrongholdCollection>,
-    snapshot_path: PathBuf,
-    client: BytesDto,
-    key: String,
-) -> Result<Option<Vec<u8>>> {
-    let client = get_client(collection, snapshot_path, client)?;
-    client.store().get(key.as_ref()).map_err(Into::into)
+/// Locks the least recently used strongholds until at most `max_loaded` are unlocked,
+/// never locking the one at `keep`.
+async fn evict<R: Runtime>(
+    app: &AppHandle<R>,
+    collection: &StrongholdCollection,
+    auto_commit: &AutoCommit,
+    pool: &BlockingPool,
+    keep: &Path,
+    max_loaded: usize,
+) {
+    let mut unlocked = collection
+        .0
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .filter(|(_, s)| !s.is_locked())
+        .map(|(path, s)| (path.clone(), s.clone()))
+        .collect::<Vec<_>>();
+    if unlocked.len() <= max_loaded {
+        return;
+    }
+    unlocked.retain(|(path, _)| path != keep);
+    unlocked.sort_by_key(|(_, s)| s.last_used());
+    let excess = unlocked.len() + 1 - max_loaded.max(1);
+
+    for (snapshot_path, stronghold) in unlocked.into_iter().take(excess) {
+        auto_commit.cancel(&snapshot_path);
+        match pool.run(move || stronghold.lock()).await {
+            Ok(()) => {
+                if let Err(e) = app.emit_all(EVICTED_EVENT, &snapshot_path) {
+                    log::error!("failed to emit {}: {}", EVICTED_EVENT, e);
+                }
+            }
+            Err(e) => log::error!("failed to lock stronghold {:?}: {}", snapshot_path, e),
+        }
+    }
 }
 
 #[tauri::command]
-async fn save_store_record(
-    collection: State<'_, StrongholdCollection>,
+async fn recover_snapshot(
+    hash_function: State<'_, PasswordHashFunction>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
     snapshot_path: PathBuf,
-    client: BytesDto,
-    key: String,
-    value: Vec<u8>,
-    lifetime: Option<Duration>,
-) -> Result<Option<Vec<u8>>> {
-    let client = get_client(collection, snapshot_path, client)?;
-    client
-        .store()
-        .insert(key.as_bytes().to_vec(), value, lifetime)
-        .map_err(Into::into)
+    mut password: String,
+) -> Result<SnapshotRecovery> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let hash_function = hash_function.0.clone();
+    pool.run(move || {
+        let hash = hash_function.hash(&password, &snapshot_path, None);
+        password.zeroize();
+        Stronghold::recover(snapshot_path, hash?)
+    })
+    .await
 }
 
+/// Runs known-answer tests of the hash, HMAC, Ed25519 and AEAD implementations, e.g. before
+/// relying on them in a compliance-sensitive setting. Does not use any snapshot.
 #[tauri::command]
-async fn remove_store_record(
-    collection: State<'_, StrongholdCollection>,
-    snapshot_path: PathBuf,
-    client: BytesDto,
-    key: String,
-) -> Result<Option<Vec<u8>>> {
-    let client = get_client(collection, snapshot_path, client)?;
-    client.store().delete(key.as_ref()).map_err(Into::into)
+async fn crypto_self_test(pool: State<'_, BlockingPool>) -> Result<self_test::SelfTestReport> {
+    pool.run(self_test::run).await
 }
 
+/// Checks `password` against the check stored next to the snapshot, without decrypting it.
+///
+/// Returns `None` if the snapshot was not saved by a version of the plugin storing the check.
 #[tauri::command]
-async fn save_secret(
-    collection: State<'_, StrongholdCollection>,
+async fn quick_password_check(
+    hash_function: State<'_, PasswordHashFunction>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
     snapshot_path: PathBuf,
-    client: BytesDto,
-    vault: BytesDto,
-    record_path: BytesDto,
-    secret: Vec<u8>,
-) -> Result<()> {
-    let client = get_client(collection, snapshot_path, client)?;
-    client
-        .vault(&vault)
-        .write_secret(Location::generic(vault, record_path), secret)
-        .map_err(Into::into)
+    mut password: String,
+) -> Result<Option<bool>> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let hash_function = hash_function.0.clone();
+    pool.run(move || {
+        let hash = hash_function.hash(&password, &snapshot_path, None);
+        password.zeroize();
+        Stronghold::check_password(&snapshot_path, hash?)
+    })
+    .await
 }
 
+/// When the snapshot was first saved, see [`Stronghold::created_at`].
 #[tauri::command]
-async fn remove_secret(
-    collection: State<'_, StrongholdCollection>,
+async fn snapshot_created_at(
+    namespace: State<'_, Namespace>,
     snapshot_path: PathBuf,
+) -> Result<SystemTime> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    Stronghold::created_at(snapshot_path)
+}
+
+/// The result of [`verify_integrity`] for one client.
+#[derive(Serialize)]
+#[cfg_attr(not(feature = "snake-case"), serde(rename_all = "camelCase"))]
+struct ClientIntegrity {
     client: BytesDto,
-    vault: BytesDto,
-    record_path: BytesDto,
-) -> Result<()> {
-    let client = get_client(collection, snapshot_path, client)?;
-    client
-        .vault(vault)
-        .delete_secret(record_path)
-        .map(|_| ())
-        .map_err(Into::into)
+    loaded: bool,
+    store_entries: usize,
+    /// The store keys whose value could not be read.
+    unreadable_store_keys: Vec<BytesDto>,
+    /// The records written by the key generation commands that are missing from their vault,
+    /// as `vault/record` with hex encoded paths.
+    missing_records: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct IntegrityReport {
+    ok: bool,
+    clients: Vec<ClientIntegrity>,
+}
+
+/// Decrypts the snapshot at `snapshot_path` and checks the given clients for store entries
+/// that cannot be read and for typed records missing from their vault.
+///
+/// The snapshot is loaded separately from the collection and is never written to.
+#[tauri::command]
+async fn verify_integrity(
+    hash_function: State<'_, PasswordHashFunction>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    mut password: String,
+    clients: Vec<BytesDto>,
+) -> Result<IntegrityReport> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let hash_function = hash_function.0.clone();
+    pool.run(move || {
+        let hash = hash_function.hash(&password, &snapshot_path, None);
+        password.zeroize();
+        let stronghold = Stronghold::load_detached(&snapshot_path, hash?)?;
+
+        let mut report = Vec::with_capacity(clients.len());
+        for client_path in clients {
+            let mut integrity = ClientIntegrity {
+                client: client_path,
+                loaded: false,
+                store_entries: 0,
+                unreadable_store_keys: Vec::new(),
+                missing_records: Vec::new(),
+            };
+            if let Ok(client) = stronghold.load_client(integrity.client.as_ref()) {
+                integrity.loaded = true;
+                let store = client.store();
+                for key in store.keys()? {
+                    if store::get(&client, &key).is_err() {
+                        integrity.unreadable_store_keys.push(BytesDto::Raw(key));
+                        continue;
+                    }
+                    if !is_record_metadata(&key) {
+                        integrity.store_entries += 1;
+                        continue;
+                    }
+                    if !key.starts_with(RECORD_TYPE_PREFIX) {
+                        continue;
+                    }
+                    let exists = record_type_location(&key)
+                        .map(|location| client.record_exists(&location))
+                        .transpose()?
+                        .unwrap_or(false);
+                    if !exists {
+                        let name = &key[RECORD_TYPE_PREFIX.len()..];
+                        integrity
+                            .missing_records
+                            .push(String::from_utf8_lossy(name).into_owned());
+                    }
+                }
+            }
+            report.push(integrity);
+        }
+
+        Ok(IntegrityReport {
+            ok: report.iter().all(|c| {
+                c.loaded && c.unreadable_store_keys.is_empty() && c.missing_records.is_empty()
+            }),
+            clients: report,
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+async fn upgrade_kdf(
+    collection: State<'_, StrongholdCollection>,
+    hash_function: State<'_, PasswordHashFunction>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    mut password: String,
+    new_params: Argon2ConfigDto,
+) -> Result<()> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let stronghold = get_stronghold(&collection, snapshot_path.clone()).await?;
+    auto_commit.cancel(&snapshot_path);
+    let hash_function = hash_function.0.clone();
+    let path = snapshot_path.clone();
+    let upgraded = pool
+        .run(move || {
+            let upgraded = hash_function.upgrade(&password, &stronghold, &path, new_params);
+            password.zeroize();
+            upgraded
+        })
+        .await?;
+
+    collection
+        .0
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(snapshot_path, Arc::new(upgraded));
+
+    Ok(())
+}
+
+/// Moves the loaded snapshot at `old_path` to `new_path`, e.g. when the app data directory is
+/// relocated, see [`Stronghold::relocate`]. The snapshot must then be used with `new_path`.
+#[tauri::command]
+async fn relocate_snapshot(
+    collection: State<'_, StrongholdCollection>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    old_path: PathBuf,
+    new_path: PathBuf,
+    overwrite: Option<bool>,
+) -> Result<()> {
+    let old_path = namespace.resolve(old_path)?;
+    let new_path = namespace.resolve(new_path)?;
+    let stronghold = get_stronghold(&collection, old_path.clone()).await?;
+    if collection
+        .0
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .contains_key(&new_path)
+    {
+        return Err(Error::SnapshotExists(new_path));
+    }
+    auto_commit.cancel(&old_path);
+    let path = new_path.clone();
+    let relocated = pool
+        .run(move || stronghold.relocate(path, overwrite.unwrap_or_default()))
+        .await?;
+
+    let mut strongholds = collection.0.lock().unwrap_or_else(PoisonError::into_inner);
+    strongholds.remove(&old_path);
+    strongholds.insert(new_path, Arc::new(relocated));
+    Ok(())
+}
+
+#[tauri::command]
+async fn destroy(
+    collection: State<'_, StrongholdCollection>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+) -> Result<()> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    auto_commit.cancel(&snapshot_path);
+    let stronghold = collection
+        .0
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(&snapshot_path);
+    if let Some(stronghold) = stronghold {
+        let s = stronghold.clone();
+        if let Err(e) = pool.run(move || s.save()).await {
+            collection
+                .0
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(snapshot_path, stronghold);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn save(
+    collection: State<'_, StrongholdCollection>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+) -> Result<()> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    auto_commit.cancel(&snapshot_path);
+    let stronghold = collection
+        .0
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&snapshot_path)
+        .cloned();
+    if let Some(stronghold) = stronghold {
+        pool.run(move || stronghold.save()).await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn reload(
+    collection: State<'_, StrongholdCollection>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+) -> Result<()> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let stronghold = get_stronghold(&collection, snapshot_path.clone()).await?;
+    auto_commit.cancel(&snapshot_path);
+    pool.run(move || stronghold.reload()).await
+}
+
+/// Measures how long saving the stronghold takes without saving it, see `testing::time_commit`,
+/// only available with the `testing` feature.
+#[tauri::command]
+#[cfg_attr(not(feature = "testing"), allow(unused_variables))]
+async fn benchmark_commit(
+    collection: State<'_, StrongholdCollection>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+) -> Result<Duration> {
+    #[cfg(feature = "testing")]
+    {
+        let snapshot_path = namespace.resolve(snapshot_path)?;
+        let stronghold = get_stronghold(&collection, snapshot_path).await?;
+        pool.run(move || testing::time_commit(&stronghold)).await
+    }
+    #[cfg(not(feature = "testing"))]
+    Err(Error::FeatureNotEnabled("testing"))
+}
+
+/// A background task spawned by the plugin for a snapshot.
+#[derive(Serialize)]
+#[cfg_attr(not(feature = "snake-case"), serde(rename_all = "camelCase"))]
+struct TaskInfo {
+    kind: TaskKind,
+    /// The time left until the task runs.
+    due_in: Duration,
+}
+
+#[derive(Serialize)]
+enum TaskKind {
+    /// A save scheduled by [`Builder::with_commit_debounce`].
+    AutoCommit,
+}
+
+#[tauri::command]
+async fn active_tasks(
+    auto_commit: State<'_, AutoCommit>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+) -> Result<Vec<TaskInfo>> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    Ok(auto_commit
+        .pending(&snapshot_path)
+        .map(|due_in| TaskInfo {
+            kind: TaskKind::AutoCommit,
+            due_in,
+        })
+        .into_iter()
+        .collect())
+}
+
+/// Stops the background tasks of the snapshot and returns how many were stopped.
+///
+/// Changes whose automatic save is cancelled stay in memory until the stronghold is saved.
+#[tauri::command]
+async fn cancel_tasks(
+    auto_commit: State<'_, AutoCommit>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+) -> Result<usize> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    Ok(usize::from(auto_commit.cancel(&snapshot_path)))
+}
+
+/// Whether a snapshot is loaded in the collection and usable, see [`Builder::with_max_loaded`].
+#[derive(Serialize)]
+enum SnapshotState {
+    NotLoaded,
+    Locked,
+    Unlocked,
+}
+
+#[tauri::command]
+async fn snapshot_state(
+    collection: State<'_, StrongholdCollection>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+) -> Result<SnapshotState> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    Ok(
+        match collection
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&snapshot_path)
+        {
+            None => SnapshotState::NotLoaded,
+            Some(stronghold) if stronghold.is_locked() => SnapshotState::Locked,
+            Some(_) => SnapshotState::Unlocked,
+        },
+    )
+}
+
+#[tauri::command]
+async fn key_fingerprint(
+    collection: State<'_, StrongholdCollection>,
+    fingerprint_hash: State<'_, FingerprintHash>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+) -> Result<String> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let stronghold = get_stronghold(&collection, snapshot_path).await?;
+    stronghold.key_fingerprint(fingerprint_hash.0)
+}
+
+#[tauri::command]
+async fn create_client(
+    collection: State<'_, StrongholdCollection>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: BytesDto,
+) -> Result<()> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let stronghold = get_stronghold(&collection, snapshot_path.clone()).await?;
+    stronghold.create_client(client)?;
+    changed(&collection, &auto_commit, &pool, snapshot_path);
+    Ok(())
+}
+
+#[tauri::command]
+async fn load_client(
+    collection: State<'_, StrongholdCollection>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: BytesDto,
+) -> Result<()> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let stronghold = get_stronghold(&collection, snapshot_path).await?;
+    stronghold.load_client(client)?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn list_clients(
+    collection: State<'_, StrongholdCollection>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+) -> Result<Vec<BytesDto>> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let stronghold = get_stronghold(&collection, snapshot_path).await?;
+    Ok(stronghold
+        .clients()
+        .into_iter()
+        .map(BytesDto::Raw)
+        .collect())
+}
+
+#[tauri::command]
+async fn client_count(
+    collection: State<'_, StrongholdCollection>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+) -> Result<usize> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let stronghold = get_stronghold(&collection, snapshot_path).await?;
+    Ok(stronghold.clients().len())
+}
+
+#[tauri::command]
+async fn get_store_record(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    key: String,
+) -> Result<Option<Vec<u8>>> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path, client).await?;
+    store::get(&client, key.as_ref())
+}
+
+/// Reads several store records at once, in the order of `keys`, while no save or reload
+/// can change the store in between.
+#[tauri::command]
+async fn get_store_records(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    keys: Vec<BytesDto>,
+) -> Result<Vec<Option<Vec<u8>>>> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let stronghold = get_stronghold(&collection, snapshot_path.clone()).await?;
+    let client = get_client(&collection, &default_client, snapshot_path, client).await?;
+    let _reading = stronghold.read_guard();
+    keys.iter()
+        .map(|key| store::get(&client, key.as_ref()))
+        .collect()
+}
+
+#[tauri::command]
+async fn save_store_record(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    store_encryption: State<'_, StoreEncryption>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    key: String,
+    value: Vec<u8>,
+    lifetime: Option<Duration>,
+) -> Result<Option<Vec<u8>>> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let stronghold = get_stronghold(&collection, snapshot_path.clone()).await?;
+    let client = get_client(&collection, &default_client, snapshot_path.clone(), client).await?;
+    let previous =
+        store_encryption.insert(&stronghold, &client, key.into_bytes(), value, lifetime)?;
+    changed(&collection, &auto_commit, &pool, snapshot_path);
+    Ok(previous)
+}
+
+#[tauri::command]
+async fn remove_store_record(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    key: String,
+) -> Result<Option<Vec<u8>>> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path.clone(), client).await?;
+    let removed = store::delete(&client, key.as_ref())?;
+    changed(&collection, &auto_commit, &pool, snapshot_path);
+    Ok(removed)
+}
+
+#[tauri::command]
+async fn store_keys(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+) -> Result<Vec<BytesDto>> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let stronghold = get_stronghold(&collection, snapshot_path.clone()).await?;
+    let client = get_client(&collection, &default_client, snapshot_path, client).await?;
+    let _reading = stronghold.read_guard();
+    let store = client.store();
+    let mut keys = Vec::new();
+    for key in store.keys()? {
+        // skip entries whose lifetime has expired but were not evicted yet,
+        // as well as the plugin's own bookkeeping entries
+        if !is_record_metadata(&key) && store.contains_key(&key)? {
+            keys.push(BytesDto::Raw(key));
+        }
+    }
+    Ok(keys)
+}
+
+/// Removes all store records, keeping the vaults and the plugin's record type entries,
+/// and returns the number of records removed.
+#[tauri::command]
+async fn store_clear(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+) -> Result<usize> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path.clone(), client).await?;
+    let store = client.store();
+    let mut removed = 0;
+    for key in store.keys()? {
+        // the values are removed without being decrypted, along with their encryption markers
+        if key.starts_with(store::ENCRYPTED_MARKER_PREFIX) {
+            store.delete(&key)?;
+        } else if !is_record_metadata(&key) && store.delete(&key)?.is_some() {
+            removed += 1;
+        }
+    }
+    changed(&collection, &auto_commit, &pool, snapshot_path);
+    Ok(removed)
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(feature = "snake-case"), serde(rename_all = "camelCase"))]
+struct StoreScanPage {
+    entries: Vec<BytesDto>,
+    /// Passed to [`store_scan`] to get the next page, `None` on the last page.
+    next_cursor: Option<String>,
+}
+
+/// Lists up to `limit` store keys in lexicographic order, starting after `cursor`.
+///
+/// The cursor is opaque to the caller: it is the hex encoded last key of the previous page.
+/// The store cannot be iterated in order, so each page collects and sorts all keys again and
+/// scanning a large store costs quadratic time overall; prefer large pages.
+#[tauri::command]
+async fn store_scan(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    cursor: Option<String>,
+    limit: usize,
+) -> Result<StoreScanPage> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    // an empty page would end the scan without a cursor to continue from
+    if limit == 0 {
+        return Err(Error::InvalidLimit(limit));
+    }
+    let after = cursor
+        .map(hex::decode)
+        .transpose()
+        .map_err(|e| Error::InvalidCursor(e.to_string()))?;
+    let stronghold = get_stronghold(&collection, snapshot_path.clone()).await?;
+    let client = get_client(&collection, &default_client, snapshot_path, client).await?;
+    let _reading = stronghold.read_guard();
+    let store = client.store();
+    let mut keys = store.keys()?;
+    keys.sort();
+
+    let mut entries = Vec::new();
+    let mut next_cursor = None;
+    for key in keys {
+        if matches!(&after, Some(after) if &key <= after) {
+            continue;
+        }
+        if is_record_metadata(&key) || !store.contains_key(&key)? {
+            continue;
+        }
+        if entries.len() == limit {
+            next_cursor = entries.last().map(|last: &BytesDto| hex::encode(last));
+            break;
+        }
+        entries.push(BytesDto::Raw(key));
+    }
+    Ok(StoreScanPage {
+        entries,
+        next_cursor,
+    })
+}
+
+/// Returns the records written or removed by the plugin since `since`, e.g. to sync only the
+/// changed records.
+///
+/// Only changes made through the plugin's commands since modification times were tracked are
+/// known; removed records are included, so check whether they still exist.
+#[tauri::command]
+async fn records_modified_since(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    since: SystemTime,
+) -> Result<Vec<LocationDto>> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let stronghold = get_stronghold(&collection, snapshot_path.clone()).await?;
+    let client = get_client(&collection, &default_client, snapshot_path, client).await?;
+    let _reading = stronghold.read_guard();
+    let since = since
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    let store = client.store();
+    let mut locations = Vec::new();
+    for key in store.keys()? {
+        let name = match key.strip_prefix(RECORD_MODIFIED_PREFIX) {
+            Some(name) => name,
+            None => continue,
+        };
+        let modified = store
+            .get(&key)?
+            .and_then(|value| <[u8; 8]>::try_from(value).ok())
+            .map(u64::from_be_bytes);
+        if matches!(modified, Some(modified) if modified >= since) {
+            locations.extend(record_location(name));
+        }
+    }
+    Ok(locations)
+}
+
+#[tauri::command]
+async fn save_secret(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    vault: BytesDto,
+    record_path: BytesDto,
+    secret: Vec<u8>,
+) -> Result<()> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path.clone(), client).await?;
+    let record_type_key = record_type_key(vault.as_ref(), record_path.as_ref());
+    client
+        .vault(&vault)
+        .write_secret(Location::generic(vault, record_path), secret)?;
+    set_record_type(&client, record_type_key, None)?;
+    changed(&collection, &auto_commit, &pool, snapshot_path);
+    Ok(())
+}
+
+#[tauri::command]
+async fn remove_secret(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    vault: BytesDto,
+    record_path: BytesDto,
+) -> Result<()> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path.clone(), client).await?;
+    let record_type_key = record_type_key(vault.as_ref(), record_path.as_ref());
+    client.vault(vault).delete_secret(record_path)?;
+    set_record_type(&client, record_type_key, None)?;
+    changed(&collection, &auto_commit, &pool, snapshot_path);
+    Ok(())
 }
 
 #[tauri::command]
 async fn execute_procedure(
     collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    procedure: ProcedureDto,
+) -> Result<Vec<u8>> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path.clone(), client).await?;
+    let record_type = procedure
+        .output()
+        .map(|(location, kind)| (location.record_type_key(), kind));
+    let output = client.execute_procedure(StrongholdProcedure::from(procedure))?;
+    // procedures without an output record, e.g. signing, leave the stronghold unchanged
+    if let Some((key, kind)) = record_type {
+        set_record_type(&client, key, Some(kind))?;
+        changed(&collection, &auto_commit, &pool, snapshot_path);
+    }
+    Ok(output.into())
+}
+
+#[tauri::command]
+async fn execute_procedures(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    procedures: Vec<ProcedureDto>,
+) -> Result<Vec<Vec<u8>>> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path.clone(), client).await?;
+    let mut wrote = false;
+    let result = run_procedures(&client, procedures, &mut wrote);
+    // the steps before a failed one are not rolled back, so their records are committed
+    if wrote {
+        changed(&collection, &auto_commit, &pool, snapshot_path);
+    }
+    result
+}
+
+/// Executes `procedures` in order, setting `wrote` once one of them wrote a record.
+fn run_procedures(
+    client: &Client,
+    procedures: Vec<ProcedureDto>,
+    wrote: &mut bool,
+) -> Result<Vec<Vec<u8>>> {
+    let mut outputs = Vec::with_capacity(procedures.len());
+    for (step, procedure) in procedures.into_iter().enumerate() {
+        let name = procedure.name();
+        let record_type = procedure
+            .output()
+            .map(|(location, kind)| (location.record_type_key(), kind));
+        let output = client
+            .execute_procedure(StrongholdProcedure::from(procedure))
+            .map_err(|e| Error::ProcedureStep {
+                step,
+                procedure: name.into(),
+                message: e.to_string(),
+            })?;
+        if let Some((key, kind)) = record_type {
+            *wrote = true;
+            set_record_type(client, key, Some(kind))?;
+        }
+        outputs.push(output.into());
+    }
+    Ok(outputs)
+}
+
+#[tauri::command]
+async fn export_public_key(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    location: LocationDto,
+    format: PublicKeyFormat,
+) -> Result<BytesDto> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path, client).await?;
+    let public_key: Vec<u8> = client
+        .execute_procedure(StrongholdProcedure::PublicKey(PublicKey {
+            ty: StrongholdKeyType::Ed25519,
+            private_key: location.into(),
+        }))?
+        .into();
+    Ok(match format {
+        PublicKeyFormat::Raw => BytesDto::Raw(public_key),
+        PublicKeyFormat::Hex => BytesDto::Text(hex::encode(public_key)),
+        PublicKeyFormat::Base64 => BytesDto::Text(STANDARD.encode(public_key)),
+    })
+}
+
+#[tauri::command]
+async fn generate_secret_key(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    location: LocationDto,
+    size: usize,
+) -> Result<()> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    if !AEAD_KEY_SIZES.contains(&size) {
+        return Err(Error::InvalidKeySize(size));
+    }
+    let client = get_client(&collection, &default_client, snapshot_path.clone(), client).await?;
+    let mut key = vec![0u8; size];
+    crypto::utils::rand::fill(&mut key)?;
+    let record_type_key = location.record_type_key();
+    let location = Location::from(location);
+    client
+        .vault(location.vault_path())
+        .write_secret(location, key)?;
+    set_record_type(&client, record_type_key, Some(KeyKind::Symmetric))?;
+    changed(&collection, &auto_commit, &pool, snapshot_path);
+    Ok(())
+}
+
+#[tauri::command]
+async fn import_seed(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    location: LocationDto,
+    seed: Vec<u8>,
+) -> Result<()> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let seed = zeroize::Zeroizing::new(seed);
+    if !SEED_LENGTHS.contains(&seed.len()) {
+        return Err(Error::InvalidSeedLength(seed.len()));
+    }
+    let client = get_client(&collection, &default_client, snapshot_path.clone(), client).await?;
+    let record_type_key = location.record_type_key();
+    let location = Location::from(location);
+    client
+        .vault(location.vault_path())
+        .write_secret(location, seed.to_vec())?;
+    set_record_type(&client, record_type_key, Some(KeyKind::Seed))?;
+    changed(&collection, &auto_commit, &pool, snapshot_path);
+    Ok(())
+}
+
+/// The encoding of a private key imported by [`import_private_key`].
+#[derive(Deserialize)]
+enum KeyImportFormat {
+    /// The 32 byte private key.
+    Raw,
+    /// An unencrypted PKCS#8 document in DER.
+    Pkcs8,
+}
+
+/// The kinds of private keys accepted by [`import_private_key`].
+#[derive(Deserialize, Clone, Copy)]
+enum PrivateKeyType {
+    Ed25519,
+    X25519,
+    Secp256k1,
+}
+
+impl PrivateKeyType {
+    fn algorithm(self) -> pkcs8::Algorithm {
+        match self {
+            Self::Ed25519 => pkcs8::Algorithm::Ed25519,
+            Self::X25519 => pkcs8::Algorithm::X25519,
+            Self::Secp256k1 => pkcs8::Algorithm::Secp256k1,
+        }
+    }
+
+    fn kind(self) -> KeyKind {
+        match self {
+            Self::Ed25519 => KeyKind::Ed25519,
+            Self::X25519 => KeyKind::X25519,
+            Self::Secp256k1 => KeyKind::Secp256k1,
+        }
+    }
+}
+
+/// Stores an existing private key at `location`, e.g. when migrating from another tool.
+///
+/// The key is checked before it is stored: keys of the wrong length, secp256k1 keys outside of
+/// the curve order and PKCS#8 documents for another algorithm are rejected.
+#[tauri::command]
+async fn import_private_key(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    location: LocationDto,
+    key_bytes: Vec<u8>,
+    key_type: PrivateKeyType,
+    format: KeyImportFormat,
+) -> Result<()> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let key_bytes = zeroize::Zeroizing::new(key_bytes);
+    let key = zeroize::Zeroizing::new(match format {
+        KeyImportFormat::Raw => key_bytes.to_vec(),
+        KeyImportFormat::Pkcs8 => pkcs8::private_key(&key_bytes, key_type.algorithm())?,
+    });
+    let key_array = <&[u8; 32]>::try_from(key.as_slice())
+        .map_err(|_| Error::InvalidPrivateKey(format!("expected 32 bytes, got {}", key.len())))?;
+    if let PrivateKeyType::Secp256k1 = key_type {
+        crypto::signatures::secp256k1_ecdsa::SecretKey::try_from_bytes(key_array)
+            .map_err(|e| Error::InvalidPrivateKey(e.to_string()))?;
+    }
+
+    let client = get_client(&collection, &default_client, snapshot_path.clone(), client).await?;
+    let record_type_key = location.record_type_key();
+    let location = Location::from(location);
+    client
+        .vault(location.vault_path())
+        .write_secret(location, key.to_vec())?;
+    set_record_type(&client, record_type_key, Some(key_type.kind()))?;
+    changed(&collection, &auto_commit, &pool, snapshot_path);
+    Ok(())
+}
+
+/// Derives a key for `context` from the master key at `master` with HKDF-SHA256
+/// and stores it at `output`, so neither key ever leaves the vault.
+#[tauri::command]
+async fn derive_key(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    master: LocationDto,
+    context: Vec<u8>,
+    output: LocationDto,
+) -> Result<()> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path.clone(), client).await?;
+    let record_type_key = output.record_type_key();
+    client.execute_procedure(StrongholdProcedure::Hkdf(Hkdf {
+        hash_type: Sha2Hash::Sha256,
+        salt: Vec::new(),
+        label: context,
+        ikm: master.into(),
+        okm: output.into(),
+    }))?;
+    set_record_type(&client, record_type_key, Some(KeyKind::Symmetric))?;
+    changed(&collection, &auto_commit, &pool, snapshot_path);
+    Ok(())
+}
+
+/// Derives a symmetric key from the shared secret at `shared_secret`, e.g. written by an X25519
+/// key exchange, with the NIST SP 800-56A concat KDF over SHA-256 and stores it at `output`.
+#[tauri::command]
+async fn concat_kdf(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
     snapshot_path: PathBuf,
-    client: BytesDto,
-    procedure: ProcedureDto,
+    client: Option<BytesDto>,
+    shared_secret: LocationDto,
+    algorithm_id: String,
+    party_info: Option<PartyInfoDto>,
+    output: LocationDto,
+) -> Result<()> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path.clone(), client).await?;
+    let party_info = party_info.unwrap_or_default();
+    let record_type_key = output.record_type_key();
+    client.execute_procedure(StrongholdProcedure::ConcatKdf(ConcatKdf {
+        hash: Sha2Hash::Sha256,
+        algorithm_id,
+        shared_secret: shared_secret.into(),
+        key_len: AEAD_KEY_SIZES[0],
+        apu: party_info.party_u,
+        apv: party_info.party_v,
+        pub_info: party_info.public,
+        priv_info: party_info.private,
+        output: output.into(),
+    }))?;
+    set_record_type(&client, record_type_key, Some(KeyKind::Symmetric))?;
+    changed(&collection, &auto_commit, &pool, snapshot_path);
+    Ok(())
+}
+
+/// A key derived by [`scan_addresses`].
+#[derive(Serialize)]
+#[cfg_attr(not(feature = "snake-case"), serde(rename_all = "camelCase"))]
+struct AddressInfo {
+    index: u32,
+    /// The full derivation chain of the key, all hardened.
+    chain: Vec<u32>,
+    /// The Ed25519 public key, to be encoded as an address by the frontend.
+    public_key: Vec<u8>,
+}
+
+/// Derives the Ed25519 public keys at `account` followed by the indices `start`
+/// to `start + gap_limit`, so the frontend can check which addresses were used, e.g. to
+/// discover accounts. `gap_limit` is at most [`MAX_GAP_LIMIT`], and all indices must be below
+/// 2^31 as they are hardened.
+///
+/// The keys are derived and their public keys computed in a single procedure chain, then
+/// removed from the vault, so the stronghold is left unchanged.
+#[tauri::command]
+async fn scan_addresses(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    seed_location: LocationDto,
+    account: Vec<u32>,
+    start: Option<u32>,
+    gap_limit: u32,
+) -> Result<Vec<AddressInfo>> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    if gap_limit == 0 || gap_limit > MAX_GAP_LIMIT {
+        return Err(Error::InvalidGapLimit(gap_limit));
+    }
+    if let Some(index) = account.iter().find(|index| **index >= HARDENED_INDEX) {
+        return Err(Error::InvalidChainIndex(*index));
+    }
+    let start = start.unwrap_or_default();
+    // the last index must not overflow into the hardened range either
+    let end = match start.checked_add(gap_limit) {
+        Some(end) if end <= HARDENED_INDEX => end,
+        // the first index in the hardened range
+        _ => return Err(Error::InvalidChainIndex(start.max(HARDENED_INDEX))),
+    };
+    let client = get_client(&collection, &default_client, snapshot_path, client).await?;
+    let indices = (start..end).collect::<Vec<_>>();
+    let seed: Location = seed_location.into();
+
+    // the records are prefixed with a random scan id, so concurrent scans use distinct records
+    let mut scan_id = [0u8; 16];
+    crypto::utils::rand::fill(&mut scan_id)?;
+    let record = |index: &u32| [&scan_id[..], &index.to_be_bytes()].concat();
+
+    let mut procedures = Vec::with_capacity(indices.len() * 2);
+    for index in &indices {
+        let chain = account.iter().copied().chain([*index]).collect::<Vec<_>>();
+        let key = Location::generic(SCAN_VAULT, record(index));
+        procedures.push(StrongholdProcedure::Slip10Derive(Slip10Derive {
+            chain: Chain::from_u32_hardened(chain),
+            input: Slip10DeriveInput::Seed(seed.clone()),
+            output: key.clone(),
+        }));
+        procedures.push(StrongholdProcedure::PublicKey(PublicKey {
+            ty: StrongholdKeyType::Ed25519,
+            private_key: key,
+        }));
+    }
+    let outputs = client.execute_procedure_chained(procedures);
+
+    // the chain may have failed before deriving all keys
+    let vault = client.vault(SCAN_VAULT);
+    for index in &indices {
+        if client.record_exists(&Location::generic(SCAN_VAULT, record(index)))? {
+            vault.delete_secret(record(index))?;
+        }
+    }
+
+    // the chain returns the chain code of each derived key followed by its public key
+    let public_keys = outputs?.into_iter().skip(1).step_by(2);
+    Ok(indices
+        .into_iter()
+        .zip(public_keys)
+        .map(|(index, public_key)| AddressInfo {
+            index,
+            chain: account.iter().copied().chain([index]).collect(),
+            public_key: public_key.into(),
+        })
+        .collect())
+}
+
+/// Generates an X25519 key pair at `location` and returns its public key encoded in base64, as
+/// used by WireGuard configurations.
+///
+/// The private key stays in the vault; it can be used for key exchanges with the
+/// `X25519DiffieHellman` procedure, which writes the shared secret to the vault as well.
+#[tauri::command]
+async fn generate_wireguard_keypair(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    location: LocationDto,
+) -> Result<String> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path.clone(), client).await?;
+    let record_type_key = location.record_type_key();
+    let location = Location::from(location);
+    client.execute_procedure(StrongholdProcedure::GenerateKey(GenerateKey {
+        ty: StrongholdKeyType::X25519,
+        output: location.clone(),
+    }))?;
+    set_record_type(&client, record_type_key, Some(KeyKind::X25519))?;
+    let public_key: Vec<u8> = client
+        .execute_procedure(StrongholdProcedure::PublicKey(PublicKey {
+            ty: StrongholdKeyType::X25519,
+            private_key: location,
+        }))?
+        .into();
+    changed(&collection, &auto_commit, &pool, snapshot_path);
+    Ok(STANDARD.encode(public_key))
+}
+
+#[tauri::command]
+async fn generate_secp256k1(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    location: LocationDto,
+) -> Result<()> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path.clone(), client).await?;
+    let record_type_key = location.record_type_key();
+    client.execute_procedure(StrongholdProcedure::GenerateKey(GenerateKey {
+        ty: StrongholdKeyType::Secp256k1Ecdsa,
+        output: location.into(),
+    }))?;
+    set_record_type(&client, record_type_key, Some(KeyKind::Secp256k1))?;
+    changed(&collection, &auto_commit, &pool, snapshot_path);
+    Ok(())
+}
+
+/// Returns the compressed SEC1 public key of the secp256k1 private key at `location`.
+#[tauri::command]
+async fn secp256k1_public_key(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    location: LocationDto,
 ) -> Result<Vec<u8>> {
-    let client = get_client(collection, snapshot_path, client)?;
-    client
-        .execute_procedure(StrongholdProcedure::from(procedure))
-        .map(Into::into)
-        .map_err(Into::into)
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path, client).await?;
+    let public_key = client.execute_procedure(StrongholdProcedure::PublicKey(PublicKey {
+        ty: StrongholdKeyType::Secp256k1Ecdsa,
+        private_key: location.into(),
+    }))?;
+    Ok(public_key.into())
+}
+
+/// Signs `msg` with the secp256k1 private key at `location` and returns the 65 byte recoverable
+/// signature, with the recovery id as last byte.
+#[tauri::command]
+async fn secp256k1_sign(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    location: LocationDto,
+    msg: Vec<u8>,
+    hash: Secp256k1HashDto,
+) -> Result<Vec<u8>> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path, client).await?;
+    let signature = client.execute_procedure(StrongholdProcedure::Secp256k1EcdsaSign(
+        Secp256k1EcdsaSign {
+            flavor: hash.into(),
+            msg,
+            private_key: location.into(),
+        },
+    ))?;
+    Ok(signature.into())
+}
+
+/// A public key attested by [`attest_public_keys`].
+#[derive(Serialize)]
+#[cfg_attr(not(feature = "snake-case"), serde(rename_all = "camelCase"))]
+struct AttestedKey {
+    location: LocationDto,
+    kind: KeyKind,
+    public_key: Vec<u8>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(feature = "snake-case"), serde(rename_all = "camelCase"))]
+struct Attestation {
+    /// `Ed25519`, or `Secp256k1Sha256` for an ECDSA signature over the SHA-256 hash of the message.
+    algorithm: &'static str,
+    signing_public_key: Vec<u8>,
+    keys: Vec<AttestedKey>,
+    /// The signed message: the attestation context followed by the kind tag, the big endian
+    /// `u16` length and the bytes of each public key, in the order of `keys`.
+    message: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+/// The kind of key in the record at `location`, as written by the key generation commands.
+fn key_kind(client: &Client, location: &LocationDto) -> Result<Option<KeyKind>> {
+    let tag = client.store().get(&location.record_type_key())?;
+    Ok(tag.and_then(|tag| tag.first().copied().and_then(KeyKind::from_tag)))
+}
+
+/// The public key of the key pair at `location`, of the given kind.
+fn public_key_of(client: &Client, location: Location, kind: KeyKind) -> Result<Vec<u8>> {
+    let ty = match kind {
+        KeyKind::Ed25519 => StrongholdKeyType::Ed25519,
+        KeyKind::X25519 => StrongholdKeyType::X25519,
+        KeyKind::Secp256k1 => StrongholdKeyType::Secp256k1Ecdsa,
+        KeyKind::Seed | KeyKind::Symmetric => {
+            return Err(Error::UnsupportedAlgorithm(format!("{kind:?} key pair")))
+        }
+    };
+    let public_key = client.execute_procedure(StrongholdProcedure::PublicKey(PublicKey {
+        ty,
+        private_key: location,
+    }))?;
+    Ok(public_key.into())
 }
 
-fn get_stronghold(
+/// Signs the public keys of the key pairs at `key_locations` with the key at `signing_location`,
+/// e.g. so a server can check which keys a device holds when it is registered.
+///
+/// All keys must have been written by the key generation commands, which record their kind.
+/// The signing key must be an Ed25519 or secp256k1 key and never leaves the vault.
+#[tauri::command]
+async fn attest_public_keys(
     collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    namespace: State<'_, Namespace>,
     snapshot_path: PathBuf,
-) -> Result<iota_stronghold::Stronghold> {
-    let collection = collection.0.lock().unwrap();
-    if let Some(stronghold) = collection.get(&snapshot_path) {
-        Ok(stronghold.inner().clone())
+    client: Option<BytesDto>,
+    signing_location: LocationDto,
+    key_locations: Vec<LocationDto>,
+) -> Result<Attestation> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path, client).await?;
+
+    let mut message = ATTESTATION_CONTEXT.to_vec();
+    let mut keys = Vec::with_capacity(key_locations.len());
+    for location in key_locations {
+        let kind = key_kind(&client, &location)?
+            .ok_or_else(|| Error::UnsupportedAlgorithm("key of unknown kind".into()))?;
+        let public_key = public_key_of(&client, location.clone().into(), kind)?;
+        message.push(kind.tag());
+        message.extend((public_key.len() as u16).to_be_bytes());
+        message.extend(&public_key);
+        keys.push(AttestedKey {
+            location,
+            kind,
+            public_key,
+        });
+    }
+
+    let signing_kind = match key_kind(&client, &signing_location)? {
+        Some(kind @ (KeyKind::Ed25519 | KeyKind::Secp256k1)) => kind,
+        _ => {
+            return Err(Error::UnsupportedAlgorithm(
+                "signing key other than Ed25519 or secp256k1".into(),
+            ))
+        }
+    };
+    let signing_location = Location::from(signing_location);
+    let signing_public_key = public_key_of(&client, signing_location.clone(), signing_kind)?;
+    let (algorithm, procedure) = if signing_kind == KeyKind::Ed25519 {
+        (
+            "Ed25519",
+            StrongholdProcedure::Ed25519Sign(Ed25519Sign {
+                private_key: signing_location,
+                msg: message.clone(),
+            }),
+        )
     } else {
-        Err(Error::StrongholdNotInitialized)
+        (
+            "Secp256k1Sha256",
+            StrongholdProcedure::Secp256k1EcdsaSign(Secp256k1EcdsaSign {
+                flavor: Secp256k1EcdsaFlavor::Sha256,
+                msg: message.clone(),
+                private_key: signing_location,
+            }),
+        )
+    };
+    let signature = client.execute_procedure(procedure)?;
+
+    Ok(Attestation {
+        algorithm,
+        signing_public_key,
+        keys,
+        message,
+        signature: signature.into(),
+    })
+}
+
+/// Computes the RFC 6238 time-based one-time password for the current time with the HMAC key
+/// at `secret`, which never leaves the vault.
+///
+/// Only the SHA-256 and SHA-512 variants are supported, as the vault procedures have no
+/// HMAC-SHA1. Most authenticator secrets, e.g. from `otpauth://` URIs without an `algorithm`,
+/// use SHA-1 and cannot be used here. The hash is required so a SHA-1 secret is never silently
+/// used with another hash, which would produce wrong codes.
+#[tauri::command]
+async fn generate_totp(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    secret: LocationDto,
+    time_step: Option<u64>,
+    digits: Option<u32>,
+    hash: TotpHashDto,
+) -> Result<String> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let digits = digits.unwrap_or(6);
+    if !OTP_DIGITS.contains(&digits) {
+        return Err(Error::InvalidOtpDigits(digits));
     }
+    let hash_type = match hash {
+        TotpHashDto::Sha1 => return Err(Error::UnsupportedAlgorithm("SHA1".into())),
+        TotpHashDto::Sha256 => Sha2Hash::Sha256,
+        TotpHashDto::Sha512 => Sha2Hash::Sha512,
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let counter = now.as_secs() / time_step.unwrap_or(30).max(1);
+
+    let client = get_client(&collection, &default_client, snapshot_path, client).await?;
+    let mac: Vec<u8> = client
+        .execute_procedure(StrongholdProcedure::Hmac(Hmac {
+            hash_type,
+            msg: counter.to_be_bytes().to_vec(),
+            key: secret.into(),
+        }))?
+        .into();
+
+    // dynamic truncation, RFC 4226 section 5.3
+    let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+    let code = u32::from_be_bytes([
+        mac[offset] & 0x7f,
+        mac[offset + 1],
+        mac[offset + 2],
+        mac[offset + 3],
+    ]) % 10u32.pow(digits);
+    Ok(format!("{:0width$}", code, width = digits as usize))
 }
 
-fn get_client(
+/// Checks whether the secret at `location` equals `candidate` without reading it out of the vault.
+#[tauri::command]
+async fn record_matches(
     collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    namespace: State<'_, Namespace>,
     snapshot_path: PathBuf,
-    client: BytesDto,
+    client: Option<BytesDto>,
+    location: LocationDto,
+    candidate: Vec<u8>,
+) -> Result<bool> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let candidate = zeroize::Zeroizing::new(candidate);
+    let client = get_client(&collection, &default_client, snapshot_path, client).await?;
+    secret_matches(&client, location.into(), &candidate)
+}
+
+/// The vault holding the keys derived by [`secret_matches`] while they are used.
+const MATCH_VAULT: &[u8] = b"tauri-plugin-stronghold:match";
+
+/// Domain separation for the keys derived by [`secret_matches`].
+const MATCH_CONTEXT: &[u8] = b"tauri-plugin-stronghold:record-matches:v1";
+
+/// Compares the secret at `location` with `candidate` in constant time.
+///
+/// The secret cannot be used as HMAC key directly, as keys padded with zeros or longer than a
+/// block and their hash give the same MACs. Instead a key is extracted from both with HKDF, where
+/// they are hashed as message, under a random salt, and their MACs over the salt are compared.
+fn secret_matches(client: &Client, location: Location, candidate: &[u8]) -> Result<bool> {
+    let mut salt = vec![0u8; 32];
+    crypto::utils::rand::fill(&mut salt)?;
+    // the salt is random, so concurrent checks use distinct records
+    let key = Location::generic(MATCH_VAULT, salt.clone());
+    let outputs = client.execute_procedure_chained(vec![
+        StrongholdProcedure::Hkdf(Hkdf {
+            hash_type: Sha2Hash::Sha256,
+            salt: salt.clone(),
+            label: MATCH_CONTEXT.to_vec(),
+            ikm: location,
+            okm: key.clone(),
+        }),
+        StrongholdProcedure::Hmac(Hmac {
+            hash_type: Sha2Hash::Sha256,
+            msg: salt.clone(),
+            key: key.clone(),
+        }),
+    ]);
+    if client.record_exists(&key)? {
+        client.vault(MATCH_VAULT).delete_secret(salt.clone())?;
+    }
+    let record_mac: Vec<u8> = outputs?.pop().map(Into::into).unwrap_or_default();
+
+    let candidate_key = hkdf_sha256(&salt, candidate, MATCH_CONTEXT);
+    let mut candidate_mac = [0u8; 32];
+    crypto::macs::hmac::HMAC_SHA256(&salt, candidate_key.as_ref(), &mut candidate_mac);
+
+    Ok(stronghold::constant_time_eq(&record_mac, &candidate_mac))
+}
+
+/// HKDF-SHA256 (RFC 5869) with a single block of output, as computed by the [`Hkdf`] procedure.
+fn hkdf_sha256(salt: &[u8], ikm: &[u8], info: &[u8]) -> zeroize::Zeroizing<[u8; 32]> {
+    let mut prk = zeroize::Zeroizing::new([0u8; 32]);
+    crypto::macs::hmac::HMAC_SHA256(ikm, salt, &mut prk);
+    let mut okm = zeroize::Zeroizing::new([0u8; 32]);
+    crypto::macs::hmac::HMAC_SHA256(&[info, &[1u8][..]].concat(), prk.as_ref(), &mut okm);
+    okm
+}
+
+/// Returns the key check value of the symmetric key at `location`: the first bytes of the
+/// ciphertext of a block of zeros encrypted under the key with an all-zero nonce, which identify
+/// the key without revealing it. The authentication tag is not part of it.
+#[tauri::command]
+async fn key_check_value(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    location: LocationDto,
+    algorithm: AeadAlgorithm,
+) -> Result<Vec<u8>> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path, client).await?;
+    let nonce = vec![0u8; algorithm.nonce_size()];
+    let output: Vec<u8> = client
+        .execute_procedure(StrongholdProcedure::AeadEncrypt(AeadEncrypt {
+            cipher: algorithm.into(),
+            associated_data: Vec::new(),
+            plaintext: vec![0u8; 16],
+            nonce,
+            key: location.into(),
+        }))?
+        .into();
+    let (_, ciphertext) = split_aead_output(&output).ok_or(Error::InvalidSealedValue)?;
+    Ok(ciphertext[..KCV_LENGTH].to_vec())
+}
+
+/// Splits the output of the `AeadEncrypt` procedure into the authentication tag and the
+/// ciphertext: unlike most AEAD implementations, the procedure puts the tag first.
+///
+/// Returns `None` if the output is shorter than a tag.
+fn split_aead_output(output: &[u8]) -> Option<(&[u8], &[u8])> {
+    (output.len() >= AEAD_TAG_SIZE).then(|| output.split_at(AEAD_TAG_SIZE))
+}
+
+/// Derives the BIP39 seed of `mnemonic` and returns it without storing it.
+///
+/// The seed is as sensitive as the mnemonic, prefer storing it in the vault with the
+/// `BIP39Recover` procedure unless it must be displayed or compared.
+#[tauri::command]
+async fn mnemonic_to_seed(
+    pool: State<'_, BlockingPool>,
+    mnemonic: String,
+    passphrase: Option<String>,
+) -> Result<Vec<u8>> {
+    pool.run(move || {
+        // both are zeroized on drop
+        let seed = bip39::mnemonic_to_seed(
+            &bip39::Mnemonic::from(mnemonic),
+            &bip39::Passphrase::from(passphrase.unwrap_or_default()),
+        );
+        Ok(seed.as_ref().to_vec())
+    })
+    .await
+}
+
+#[tauri::command]
+fn bip39_languages() -> Vec<Bip39Language> {
+    Bip39Language::ALL.to_vec()
+}
+
+/// Checks that `mnemonic` only contains words of the `language` wordlist and that its checksum
+/// is valid. A checksum mismatch returns `false`, any other problem an error.
+#[tauri::command]
+async fn validate_mnemonic(mnemonic: String, language: Option<Bip39Language>) -> Result<bool> {
+    let mnemonic = bip39::Mnemonic::from(mnemonic);
+    let wordlist = language.unwrap_or(Bip39Language::English).wordlist();
+    match bip39::wordlist::verify(&mnemonic, wordlist) {
+        Ok(()) => Ok(true),
+        Err(bip39::wordlist::Error::ChecksumMismatch) => Ok(false),
+        Err(e) => Err(Error::InvalidMnemonic(e.to_string())),
+    }
+}
+
+#[tauri::command]
+async fn generate_nonce(algorithm: AeadAlgorithm) -> Result<Vec<u8>> {
+    let mut nonce = vec![0u8; algorithm.nonce_size()];
+    crypto::utils::rand::fill(&mut nonce)?;
+    Ok(nonce)
+}
+
+#[tauri::command]
+async fn save_record_from_file(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    auto_commit: State<'_, AutoCommit>,
+    pool: State<'_, BlockingPool>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    location: LocationDto,
+    file_path: PathBuf,
+) -> Result<()> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path.clone(), client).await?;
+    let record_type_key = location.record_type_key();
+    let location = Location::from(location);
+    pool.run(move || {
+        let secret = std::fs::read(&file_path).map_err(|e| Error::FileAccess {
+            path: file_path,
+            reason: e.to_string(),
+        })?;
+        client
+            .vault(location.vault_path())
+            .write_secret(location, secret)?;
+        set_record_type(&client, record_type_key, None)
+    })
+    .await?;
+    changed(&collection, &auto_commit, &pool, snapshot_path);
+    Ok(())
+}
+
+/// Records a change to the stronghold at `snapshot_path` so it is saved by [`AutoCommit`].
+fn changed(
+    collection: &StrongholdCollection,
+    auto_commit: &AutoCommit,
+    pool: &BlockingPool,
+    snapshot_path: PathBuf,
+) {
+    let stronghold = collection
+        .0
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&snapshot_path)
+        .cloned();
+    if let Some(stronghold) = stronghold {
+        stronghold.mark_dirty();
+        auto_commit.changed(snapshot_path, stronghold, pool.clone());
+    }
+}
+
+#[tauri::command]
+async fn record_type(
+    collection: State<'_, StrongholdCollection>,
+    default_client: State<'_, DefaultClient>,
+    namespace: State<'_, Namespace>,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
+    location: LocationDto,
+) -> Result<Option<KeyKind>> {
+    let snapshot_path = namespace.resolve(snapshot_path)?;
+    let client = get_client(&collection, &default_client, snapshot_path, client).await?;
+    key_kind(&client, &location)
+}
+
+async fn get_stronghold(
+    collection: &StrongholdCollection,
+    snapshot_path: PathBuf,
+) -> Result<Arc<Stronghold>> {
+    let stronghold = get_loaded(collection, snapshot_path).await?;
+    stronghold.touch();
+    Ok(stronghold)
+}
+
+async fn get_client(
+    collection: &StrongholdCollection,
+    default_client: &DefaultClient,
+    snapshot_path: PathBuf,
+    client: Option<BytesDto>,
 ) -> Result<Client> {
-    let collection = collection.0.lock().unwrap();
-    if let Some(stronghold) = collection.get(&snapshot_path) {
-        stronghold.get_client(client).map_err(Into::into)
-    } else {
-        Err(Error::StrongholdNotInitialized)
+    let client = client
+        .or_else(|| default_client.0.clone().map(BytesDto::Raw))
+        .ok_or(Error::ClientNotSpecified)?;
+    let stronghold = get_loaded(collection, snapshot_path).await?;
+    stronghold.touch();
+    stronghold.get_client(client).map_err(Into::into)
+}
+
+/// Returns the unlocked stronghold at `snapshot_path`, waiting up to the busy timeout
+/// if it is still being loaded.
+async fn get_loaded(
+    collection: &StrongholdCollection,
+    snapshot_path: PathBuf,
+) -> Result<Arc<Stronghold>> {
+    let deadline = Instant::now() + collection.1.timeout;
+    loop {
+        {
+            let strongholds = collection.0.lock().unwrap_or_else(PoisonError::into_inner);
+            match strongholds.get(&snapshot_path) {
+                Some(stronghold) if stronghold.is_locked() => {
+                    return Err(Error::Locked(snapshot_path))
+                }
+                Some(stronghold) => return Ok(stronghold.clone()),
+                // `initialize` inserts the stronghold before it stops marking it as loading,
+                // so while the collection is locked a missing stronghold is either loading or
+                // not initialized
+                None if !collection.1.contains(&snapshot_path) => {
+                    return Err(Error::StrongholdNotInitialized)
+                }
+                None => (),
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err(Error::Busy(snapshot_path));
+        }
+        tokio::time::sleep(BUSY_POLL_INTERVAL).await;
     }
 }
 
+#[derive(Clone)]
 enum PasswordHashFunctionKind {
     #[cfg(feature = "kdf")]
-    Argon2(PathBuf),
-    Custom(Box<PasswordHashFn>),
+    /// The salt file, the default cost parameters and the namespace mixed into the salt.
+    Argon2(PathBuf, kdf::Argon2Config, Option<String>),
+    Custom(Arc<PasswordHashFn>),
+}
+
+impl PasswordHashFunctionKind {
+    /// Hashes `password` for the snapshot at `snapshot_path`, optionally with a specific salt.
+    #[cfg_attr(not(feature = "kdf"), allow(unused_variables))]
+    fn hash(&self, password: &str, snapshot_path: &Path, salt: Option<Vec<u8>>) -> Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "kdf")]
+            Self::Argon2(salt_path, config, namespace) => {
+                let config = kdf::snapshot_config(snapshot_path)?.unwrap_or(*config);
+                kdf::argon2_for_snapshot(
+                    password,
+                    snapshot_path,
+                    salt_path,
+                    salt,
+                    namespace.as_deref(),
+                    &config,
+                )
+            }
+            Self::Custom(f) => {
+                if salt.is_some() {
+                    return Err(Error::SaltNotSupported);
+                }
+                Ok(f(password))
+            }
+        }
+    }
+}
+
+/// The password hash function with the pepper mixed into its output, if any.
+#[derive(Clone)]
+struct PasswordHasher {
+    kind: PasswordHashFunctionKind,
+    pepper: Option<Arc<PepperFn>>,
+}
+
+impl PasswordHasher {
+    /// Hashes `password` for the snapshot at `snapshot_path`, optionally with a specific salt.
+    ///
+    /// Leaves the files of the snapshot untouched, so an interrupted [`Self::upgrade`] is hashed
+    /// with the parameters from before the upgrade, see [`Self::hash_for_load`].
+    fn hash(&self, password: &str, snapshot_path: &Path, salt: Option<Vec<u8>>) -> Result<Vec<u8>> {
+        let hash = self.kind.hash(password, snapshot_path, salt)?;
+        Ok(self.with_pepper(hash))
+    }
+
+    /// Same as [`Self::hash`], after completing or rolling back an interrupted [`Self::upgrade`]
+    /// so the hash unlocks the snapshot about to be loaded.
+    fn hash_for_load(
+        &self,
+        password: &str,
+        snapshot_path: &Path,
+        salt: Option<Vec<u8>>,
+    ) -> Result<Vec<u8>> {
+        #[cfg(feature = "kdf")]
+        self.recover_upgrade(password, snapshot_path, &salt)?;
+        self.hash(password, snapshot_path, salt)
+    }
+
+    /// Completes or rolls back an interrupted [`Self::upgrade`] of the snapshot at
+    /// `snapshot_path`, keeping the argon2 parameters that unlock the snapshot.
+    ///
+    /// With a wrong password neither does, and the upgrade is left to the next attempt.
+    #[cfg(feature = "kdf")]
+    fn recover_upgrade(
+        &self,
+        password: &str,
+        snapshot_path: &Path,
+        salt: &Option<Vec<u8>>,
+    ) -> Result<()> {
+        let (salt_path, config, namespace) = match &self.kind {
+            PasswordHashFunctionKind::Argon2(salt_path, config, namespace) => {
+                (salt_path, config, namespace)
+            }
+            PasswordHashFunctionKind::Custom(_) => return Ok(()),
+        };
+        let staged = match kdf::staged_snapshot_config(snapshot_path)? {
+            Some(staged) => staged,
+            None => return Ok(()),
+        };
+        let unlocks = |config: &kdf::Argon2Config| -> Result<bool> {
+            let hash = self.with_pepper(kdf::argon2_for_snapshot(
+                password,
+                snapshot_path,
+                salt_path,
+                salt.clone(),
+                namespace.as_deref(),
+                config,
+            )?);
+            // an interrupted save of the snapshot is completed with the key it was written with
+            Ok(Stronghold::recover(snapshot_path, hash.clone()).is_ok()
+                && Stronghold::load_detached(snapshot_path, hash).is_ok())
+        };
+
+        // the upgraded snapshot is tried first, so recovering its interrupted save completes it
+        // rather than discarding it
+        if unlocks(&staged)? {
+            kdf::commit_snapshot_config(snapshot_path)
+        } else if unlocks(&kdf::snapshot_config(snapshot_path)?.unwrap_or(*config))? {
+            kdf::discard_staged_snapshot_config(snapshot_path)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Mixes the pepper into `hash` with HMAC-SHA256, keyed with the pepper.
+    fn with_pepper(&self, hash: Vec<u8>) -> Vec<u8> {
+        match &self.pepper {
+            Some(pepper) => {
+                let hash = zeroize::Zeroizing::new(hash);
+                let pepper = zeroize::Zeroizing::new(pepper());
+                let mut peppered = [0u8; 32];
+                crypto::macs::hmac::HMAC_SHA256(&hash, &pepper, &mut peppered);
+                let peppered_hash = peppered.to_vec();
+                peppered.zeroize();
+                peppered_hash
+            }
+            None => hash,
+        }
+    }
+
+    /// Re-encrypts `stronghold` with a password hash using the `params` cost parameters,
+    /// which are then used whenever the snapshot is loaded.
+    #[cfg_attr(not(feature = "kdf"), allow(unused_variables))]
+    fn upgrade(
+        &self,
+        password: &str,
+        stronghold: &Stronghold,
+        snapshot_path: &Path,
+        params: Argon2ConfigDto,
+    ) -> Result<Stronghold> {
+        match &self.kind {
+            #[cfg(feature = "kdf")]
+            PasswordHashFunctionKind::Argon2(salt_path, _, namespace) => {
+                let config = kdf::Argon2Config::from(params);
+                config.validate()?;
+                if stronghold.is_locked() {
+                    return Err(Error::Locked(snapshot_path.to_path_buf()));
+                }
+                let current = self.hash(password, snapshot_path, None)?;
+                let upgraded = kdf::argon2_for_snapshot(
+                    password,
+                    snapshot_path,
+                    salt_path,
+                    None,
+                    namespace.as_deref(),
+                    &config,
+                )?;
+                // checked before anything is staged, so a wrong password leaves no parameters
+                // behind for every later load to try
+                stronghold.save()?;
+                Stronghold::load_detached(snapshot_path, current.clone())?;
+
+                // staged before the snapshot is re-encrypted, so a load after a crash in between
+                // can tell which parameters the snapshot is saved with
+                if let Err(e) = kdf::stage_snapshot_config(snapshot_path, &config) {
+                    let _ = kdf::discard_staged_snapshot_config(snapshot_path);
+                    return Err(e);
+                }
+                let upgraded = stronghold
+                    .change_password(current, self.with_pepper(upgraded))
+                    .and_then(|stronghold| {
+                        kdf::commit_snapshot_config(snapshot_path)?;
+                        Ok(stronghold)
+                    });
+                if upgraded.is_err() {
+                    // the snapshot may have been re-encrypted before the failure, so the staged
+                    // parameters are kept or discarded depending on which ones unlock it
+                    if let Err(e) = self.recover_upgrade(password, snapshot_path, &None) {
+                        log::error!("failed to clean up the interrupted KDF upgrade: {}", e);
+                    }
+                }
+                upgraded
+            }
+            PasswordHashFunctionKind::Custom(_) => Err(Error::KdfUpgradeNotSupported),
+        }
+    }
 }
 
 pub struct Builder {
     password_hash_function: PasswordHashFunctionKind,
+    blocking_pool_size: Option<usize>,
+    default_client: Option<Vec<u8>>,
+    file_mode: u32,
+    commit_retries: u32,
+    commit_debounce: Option<Duration>,
+    max_loaded: Option<usize>,
+    namespace: Option<String>,
+    fingerprint_hash: HashAlgorithm,
+    encrypted_store: bool,
+    busy_timeout: Duration,
+    pepper: Option<Arc<PepperFn>>,
 }
 
 impl Builder {
     pub fn new<F: Fn(&str) -> Vec<u8> + Send + Sync + 'static>(password_hash_function: F) -> Self {
         Self {
-            password_hash_function: PasswordHashFunctionKind::Custom(Box::new(
+            password_hash_function: PasswordHashFunctionKind::Custom(Arc::new(
                 password_hash_function,
             )),
+            blocking_pool_size: None,
+            default_client: None,
+            file_mode: stronghold::DEFAULT_FILE_MODE,
+            commit_retries: 0,
+            commit_debounce: None,
+            max_loaded: None,
+            namespace: None,
+            fingerprint_hash: HashAlgorithm::default(),
+            encrypted_store: false,
+            busy_timeout: DEFAULT_BUSY_TIMEOUT,
+            pepper: None,
         }
     }
 
@@ -435,24 +2832,253 @@ impl Builder {
     #[cfg(feature = "kdf")]
     pub fn with_argon2(salt_path: &std::path::Path) -> Self {
         Self {
-            password_hash_function: PasswordHashFunctionKind::Argon2(salt_path.to_owned()),
+            password_hash_function: PasswordHashFunctionKind::Argon2(
+                salt_path.to_owned(),
+                Default::default(),
+                None,
+            ),
+            blocking_pool_size: None,
+            default_client: None,
+            file_mode: stronghold::DEFAULT_FILE_MODE,
+            commit_retries: 0,
+            commit_debounce: None,
+            max_loaded: None,
+            namespace: None,
+            fingerprint_hash: HashAlgorithm::default(),
+            encrypted_store: false,
+            busy_timeout: DEFAULT_BUSY_TIMEOUT,
+            pepper: None,
+        }
+    }
+
+    /// Initializes [`Self`] with argon2 as password hash function, using the given cost parameters.
+    ///
+    /// Returns an error if the parameters are rejected by [`kdf::Argon2Config::validate`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tauri_plugin_stronghold::kdf::{Argon2Config, Variant};
+    ///
+    /// tauri::Builder::default()
+    ///     .setup(|app| {
+    ///         let salt_path = app
+    ///             .path_resolver()
+    ///             .app_local_data_dir()
+    ///             .expect("could not resolve app local data path")
+    ///             .join("salt.txt");
+    ///         let config = Argon2Config {
+    ///             mem_cost: 10_000,
+    ///             time_cost: 10,
+    ///             lanes: 4,
+    ///             variant: Variant::Argon2id,
+    ///         };
+    ///         app.handle().plugin(
+    ///             tauri_plugin_stronghold::Builder::with_argon2_config(&salt_path, config)?.build(),
+    ///         )?;
+    ///         Ok(())
+    ///     });
+    /// ```
+    #[cfg(feature = "kdf")]
+    pub fn with_argon2_config(
+        salt_path: &std::path::Path,
+        config: kdf::Argon2Config,
+    ) -> Result<Self> {
+        config.validate()?;
+        let mut builder = Self::with_argon2(salt_path);
+        builder.password_hash_function =
+            PasswordHashFunctionKind::Argon2(salt_path.to_owned(), config, None);
+        Ok(builder)
+    }
+
+    /// Mixes a pepper, e.g. a secret provided by the server or held in a hardware keystore, into
+    /// the password hash, so a stolen snapshot cannot be unlocked with the password alone.
+    ///
+    /// `pepper` is called each time a password is hashed and must always return the same value:
+    /// snapshots saved with a pepper can only be unlocked with it, and there is no way to
+    /// recover them if it is lost, even with the correct password. Adding a pepper to an
+    /// existing setup makes its snapshots unreadable as well.
+    ///
+    /// Snapshots unlocked with key shares instead of a password bypass the pepper, as the shares
+    /// reconstruct the snapshot key itself, see `split_key`.
+    pub fn with_pepper_provider<F: Fn() -> Vec<u8> + Send + Sync + 'static>(
+        mut self,
+        pepper: F,
+    ) -> Self {
+        self.pepper.replace(Arc::new(pepper));
+        self
+    }
+
+    /// Sets how many password hashing and snapshot commit operations may run at the same time.
+    ///
+    /// These operations are CPU-heavy and run on dedicated blocking threads so they do not stall
+    /// the async runtime. Defaults to the available parallelism of the system.
+    pub fn with_blocking_pool_size(mut self, size: usize) -> Self {
+        self.blocking_pool_size.replace(size);
+        self
+    }
+
+    /// Sets the client used by the store, vault and procedure commands when they are invoked
+    /// without a `client` argument.
+    ///
+    /// A `client` passed to a command always takes precedence over this default.
+    pub fn with_default_client(mut self, client: Vec<u8>) -> Self {
+        self.default_client.replace(client);
+        self
+    }
+
+    /// Sets the unix permissions applied to snapshot files each time they are saved.
+    ///
+    /// Defaults to `0o600` so other local users cannot read the snapshot. Has no effect on Windows.
+    pub fn with_file_mode(mut self, mode: u32) -> Self {
+        self.file_mode = mode;
+        self
+    }
+
+    /// Sets how many times a failed snapshot save is retried, with a short delay in between,
+    /// before the error is returned.
+    ///
+    /// Useful on storage that is temporarily unavailable at times, e.g. on mobile during backups.
+    /// The saves of pending changes when the app exits are not retried, so exiting never stalls.
+    /// Defaults to no retries.
+    pub fn with_commit_retries(mut self, retries: u32) -> Self {
+        self.commit_retries = retries;
+        self
+    }
+
+    /// Saves strongholds automatically once they were not changed for `delay`.
+    ///
+    /// Changes made by the record, store, procedure and client creation commands schedule a save,
+    /// so a burst of writes results in a single commit. Pending saves are done right away when
+    /// the stronghold is saved explicitly or unloaded, and when the app exits.
+    ///
+    /// A failed save emits `stronghold://commit-error` with the snapshot path and the reason,
+    /// as there is no command left to return the error to.
+    pub fn with_commit_debounce(mut self, delay: Duration) -> Self {
+        self.commit_debounce.replace(delay);
+        self
+    }
+
+    /// Sets how long commands wait for a snapshot that is still being loaded by `initialize`
+    /// before failing with [`Error::Busy`]. Defaults to one second.
+    ///
+    /// Commands using a snapshot that is neither loaded nor loading fail with
+    /// [`Error::StrongholdNotInitialized`] right away.
+    pub fn with_busy_timeout(mut self, timeout: Duration) -> Self {
+        self.busy_timeout = timeout;
+        self
+    }
+
+    /// Limits how many strongholds are kept unlocked at the same time.
+    ///
+    /// When a snapshot is loaded while `max` strongholds are already unlocked, the least recently
+    /// used one is saved if it has unsaved changes and locked, emitting `stronghold://evicted`
+    /// with its snapshot path. Using a locked stronghold fails with [`Error::Locked`] until its
+    /// snapshot is loaded again. Defaults to no limit.
+    pub fn with_max_loaded(mut self, max: usize) -> Self {
+        self.max_loaded.replace(max);
+        self
+    }
+
+    /// Resolves relative snapshot paths in the `namespace` directory, e.g. the app identifier,
+    /// so apps sharing a base directory do not use each other's snapshots.
+    ///
+    /// With the argon2 password hash the namespace is also mixed into the salt, so a snapshot
+    /// cannot be unlocked under another namespace. Absolute snapshot paths are used as is, and
+    /// relative ones with `..` components are rejected.
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        let namespace = namespace.into();
+        #[cfg(feature = "kdf")]
+        if let PasswordHashFunctionKind::Argon2(_, _, salt_namespace) =
+            &mut self.password_hash_function
+        {
+            salt_namespace.replace(namespace.clone());
         }
+        self.namespace.replace(namespace);
+        self
+    }
+
+    /// Sets the hash used to compute key fingerprints. Defaults to SHA-256.
+    pub fn with_fingerprint_hash(mut self, hash: HashAlgorithm) -> Self {
+        self.fingerprint_hash = hash;
+        self
+    }
+
+    /// Encrypts the values written by the store commands with a random data key kept in a
+    /// reserved vault of each client, so store data is protected beyond the snapshot encryption.
+    /// Each value is bound to its store key, so values cannot be swapped between keys unnoticed.
+    ///
+    /// The data key is not derived from the snapshot key and cannot be rotated.
+    ///
+    /// Encrypted values are decrypted transparently by the store commands, also when this is
+    /// disabled again; values written before it was enabled are read as is. Defaults to `false`.
+    pub fn with_encrypted_store(mut self, enabled: bool) -> Self {
+        self.encrypted_store = enabled;
+        self
     }
 
     pub fn build<R: Runtime>(self) -> TauriPlugin<R> {
         let password_hash_function = self.password_hash_function;
+        let blocking_pool_size = self.blocking_pool_size;
+        let default_client = self.default_client;
+        let file_mode = self.file_mode;
+        let commit_retries = self.commit_retries;
+        let commit_debounce = self.commit_debounce;
+        let max_loaded = self.max_loaded;
+        let namespace = self.namespace;
+        let fingerprint_hash = self.fingerprint_hash;
+        let encrypted_store = self.encrypted_store;
+        let busy_timeout = self.busy_timeout;
+        let pepper = self.pepper;
 
-        let plugin_builder = PluginBuilder::new("stronghold").setup(move |app| {
-            app.manage(StrongholdCollection::default());
-            app.manage(PasswordHashFunction(match password_hash_function {
-                #[cfg(feature = "kdf")]
-                PasswordHashFunctionKind::Argon2(path) => {
-                    Box::new(move |p| kdf::KeyDerivation::argon2(p, &path))
+        let plugin_builder = PluginBuilder::new("stronghold")
+            .setup(move |app| {
+                app.manage(StrongholdCollection(
+                    Default::default(),
+                    Arc::new(Loading {
+                        paths: Default::default(),
+                        timeout: busy_timeout,
+                    }),
+                ));
+                app.manage(DefaultClient(default_client));
+                app.manage(FileMode(file_mode));
+                app.manage(CommitRetries(commit_retries));
+                app.manage(MaxLoaded(max_loaded));
+                app.manage(Namespace(namespace));
+                app.manage(FingerprintHash(fingerprint_hash));
+                app.manage(StoreEncryption(encrypted_store));
+                app.manage(
+                    blocking_pool_size
+                        .map(BlockingPool::new)
+                        .unwrap_or_default(),
+                );
+                app.manage(PasswordHashFunction(PasswordHasher {
+                    kind: password_hash_function,
+                    pepper,
+                }));
+                let app_handle = app.clone();
+                app.manage(
+                    commit_debounce
+                        .map(|delay| {
+                            AutoCommit::new(delay, move |snapshot_path, e| {
+                                let payload = CommitErrorPayload {
+                                    snapshot_path: snapshot_path.to_path_buf(),
+                                    reason: e.to_string(),
+                                };
+                                if let Err(e) = app_handle.emit_all(COMMIT_ERROR_EVENT, payload) {
+                                    log::error!("failed to emit {}: {}", COMMIT_ERROR_EVENT, e);
+                                }
+                            })
+                        })
+                        .unwrap_or_default(),
+                );
+                Ok(())
+            })
+            .on_event(|app, event| {
+                if let RunEvent::Exit = event {
+                    app.state::<AutoCommit>().flush();
                 }
-                PasswordHashFunctionKind::Custom(f) => f,
-            }));
-            Ok(())
-        });
+            });
 
         Builder::invoke_stronghold_handlers_and_build(plugin_builder)
     }
@@ -462,18 +3088,198 @@ impl Builder {
     ) -> TauriPlugin<R> {
         builder
             .invoke_handler(tauri::generate_handler![
+                version,
+                p2p_available,
+                features,
+                snapshot_debug_info,
                 initialize,
+                split_key,
+                combine_shares,
+                recover_snapshot,
+                verify_integrity,
+                quick_password_check,
+                crypto_self_test,
+                upgrade_kdf,
+                relocate_snapshot,
                 destroy,
                 save,
+                reload,
+                snapshot_state,
+                snapshot_created_at,
+                active_tasks,
+                cancel_tasks,
+                key_fingerprint,
+                benchmark_commit,
                 create_client,
                 load_client,
+                list_clients,
+                client_count,
                 get_store_record,
+                get_store_records,
                 save_store_record,
                 remove_store_record,
+                store_keys,
+                store_scan,
+                store_clear,
+                records_modified_since,
                 save_secret,
                 remove_secret,
                 execute_procedure,
+                execute_procedures,
+                export_public_key,
+                generate_secret_key,
+                scan_addresses,
+                generate_wireguard_keypair,
+                generate_secp256k1,
+                secp256k1_public_key,
+                secp256k1_sign,
+                generate_nonce,
+                mnemonic_to_seed,
+                validate_mnemonic,
+                bip39_languages,
+                key_check_value,
+                attest_public_keys,
+                generate_totp,
+                record_matches,
+                import_seed,
+                import_private_key,
+                derive_key,
+                concat_kdf,
+                save_record_from_file,
+                record_type,
             ])
             .build()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client_with_secret(secret: &[u8]) -> (iota_stronghold::Stronghold, Location) {
+        let stronghold = iota_stronghold::Stronghold::default();
+        let client = stronghold.create_client(b"client").unwrap();
+        let location = Location::generic(b"vault".to_vec(), b"record".to_vec());
+        client
+            .vault(b"vault")
+            .write_secret(location.clone(), secret.to_vec())
+            .unwrap();
+        (stronghold, location)
+    }
+
+    #[test]
+    fn record_type_keys_of_generic_and_counter_records_differ() {
+        let generic = record_type_key(b"vault", b"#1");
+        let counter = counter_record_type_key(b"vault", 1);
+        assert_ne!(generic, counter);
+
+        let name = |key: &[u8]| record_location(&key[RECORD_TYPE_PREFIX.len()..]);
+        match name(&generic) {
+            Some(LocationDto::Generic { record, .. }) => assert_eq!(record.as_ref(), b"#1"),
+            _ => panic!("not parsed as a generic record"),
+        }
+        match name(&counter) {
+            Some(LocationDto::Counter { counter, .. }) => assert_eq!(counter, 1),
+            _ => panic!("not parsed as a counter record"),
+        }
+    }
+
+    #[cfg(feature = "kdf")]
+    #[test]
+    fn create_new_replaces_the_snapshot_salt() {
+        let snapshot = crate::testing::TempSnapshot::new();
+        let salt_file = crate::testing::TempSnapshot::new();
+        let hasher = PasswordHasher {
+            kind: PasswordHashFunctionKind::Argon2(
+                salt_file.path().to_path_buf(),
+                kdf::Argon2Config::default(),
+                None,
+            ),
+            pepper: None,
+        };
+        let open = |salt: &[u8], create_new| {
+            open_stronghold(
+                &hasher,
+                snapshot.path().to_path_buf(),
+                Some("password"),
+                Some(salt.to_vec()),
+                create_new,
+                None,
+            )
+        };
+
+        open(b"first salt", false).unwrap().save().unwrap();
+        assert!(matches!(
+            open(b"other salt", false),
+            Err(Error::SaltMismatch)
+        ));
+
+        open(b"second salt", true).unwrap().save().unwrap();
+        assert!(open(b"second salt", false).is_ok());
+        assert!(matches!(
+            open(b"first salt", false),
+            Err(Error::SaltMismatch)
+        ));
+    }
+
+    #[test]
+    fn namespaced_paths_cannot_leave_the_namespace() {
+        let namespace = Namespace(Some("app".into()));
+        assert_eq!(
+            namespace.resolve("vault.hold".into()).unwrap(),
+            Path::new("app").join("vault.hold")
+        );
+        let absolute = std::env::temp_dir().join("vault.hold");
+        assert_eq!(namespace.resolve(absolute.clone()).unwrap(), absolute);
+        assert!(matches!(
+            namespace.resolve("../other-app/vault.hold".into()),
+            Err(Error::InvalidSnapshotPath(_))
+        ));
+        assert!(matches!(
+            namespace.resolve("dir/../../vault.hold".into()),
+            Err(Error::InvalidSnapshotPath(_))
+        ));
+    }
+
+    #[test]
+    fn hkdf_sha256_matches_rfc5869() {
+        // RFC 5869, test case 1, first block of the output
+        let okm = hkdf_sha256(
+            &hex::decode("000102030405060708090a0b0c").unwrap(),
+            &[0x0b; 22],
+            &hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap(),
+        );
+        assert_eq!(
+            hex::encode(okm.as_ref()),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf"
+        );
+    }
+
+    #[test]
+    fn secret_matches_only_the_same_secret() {
+        let (stronghold, location) = client_with_secret(b"secret");
+        let client = stronghold.get_client(b"client").unwrap();
+        assert!(secret_matches(&client, location.clone(), b"secret").unwrap());
+        assert!(!secret_matches(&client, location.clone(), b"secreT").unwrap());
+        assert!(!secret_matches(&client, location, b"").unwrap());
+    }
+
+    #[test]
+    fn secret_matches_rejects_padded_candidates() {
+        let (stronghold, location) = client_with_secret(b"secret");
+        let client = stronghold.get_client(b"client").unwrap();
+        // equal as HMAC keys
+        assert!(!secret_matches(&client, location.clone(), b"secret\0").unwrap());
+        assert!(!secret_matches(&client, location, b"secret\0\0\0\0").unwrap());
+
+        let long = vec![0x42; 100];
+        let (stronghold, location) = client_with_secret(&long);
+        let client = stronghold.get_client(b"client").unwrap();
+        let hashed = {
+            use crypto::hashes::{sha::Sha256, Digest};
+            Sha256::digest(&long)
+        };
+        assert!(!secret_matches(&client, location.clone(), &hashed).unwrap());
+        assert!(secret_matches(&client, location, &long).unwrap());
+    }
+}
@@ -0,0 +1,206 @@
+use crate::stronghold::{Error, Result};
+
+const SEQUENCE: u8 = 0x30;
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const OBJECT_IDENTIFIER: u8 = 0x06;
+
+/// 1.3.101.112
+const ED25519_OID: &[u8] = &[0x2b, 0x65, 0x70];
+/// 1.3.101.110
+const X25519_OID: &[u8] = &[0x2b, 0x65, 0x6e];
+/// 1.2.840.10045.2.1
+const EC_PUBLIC_KEY_OID: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+/// 1.3.132.0.10
+const SECP256K1_OID: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x0a];
+
+/// The algorithms of the private keys that can be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Algorithm {
+    Ed25519,
+    X25519,
+    Secp256k1,
+}
+
+/// Returns the raw private key of the unencrypted PKCS#8 document `der` (RFC 5208), which must
+/// hold a key of `algorithm` as per RFC 8410 or RFC 5915.
+///
+/// Attributes and public keys in the document are ignored.
+pub(crate) fn private_key(der: &[u8], algorithm: Algorithm) -> Result<Vec<u8>> {
+    let (info, rest) = read(der, SEQUENCE)?;
+    if !rest.is_empty() {
+        return Err(invalid("trailing data"));
+    }
+    let (version, info) = read(info, INTEGER)?;
+    if version != [0] && version != [1] {
+        return Err(invalid("unsupported version"));
+    }
+
+    let (identifier, info) = read(info, SEQUENCE)?;
+    let (oid, parameters) = read(identifier, OBJECT_IDENTIFIER)?;
+    let (private_key, _) = read(info, OCTET_STRING)?;
+
+    match algorithm {
+        Algorithm::Ed25519 | Algorithm::X25519 => {
+            let expected = if algorithm == Algorithm::Ed25519 {
+                ED25519_OID
+            } else {
+                X25519_OID
+            };
+            if oid != expected {
+                return Err(invalid(&format!("not an {algorithm:?} key")));
+            }
+            // CurvePrivateKey ::= OCTET STRING
+            let (key, rest) = read(private_key, OCTET_STRING)?;
+            if !rest.is_empty() {
+                return Err(invalid("trailing data after the private key"));
+            }
+            Ok(key.to_vec())
+        }
+        Algorithm::Secp256k1 => {
+            let curve = match read(parameters, OBJECT_IDENTIFIER) {
+                Ok((curve, _)) => curve,
+                Err(_) => return Err(invalid("missing curve parameters")),
+            };
+            if oid != EC_PUBLIC_KEY_OID || curve != SECP256K1_OID {
+                return Err(invalid("not a secp256k1 key"));
+            }
+            // ECPrivateKey ::= SEQUENCE { version INTEGER (1), privateKey OCTET STRING, ... }
+            let (ec_private_key, _) = read(private_key, SEQUENCE)?;
+            let (version, ec_private_key) = read(ec_private_key, INTEGER)?;
+            if version != [1] {
+                return Err(invalid("unsupported EC private key version"));
+            }
+            let (key, _) = read(ec_private_key, OCTET_STRING)?;
+            Ok(key.to_vec())
+        }
+    }
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidPrivateKey(format!("malformed PKCS#8 document: {reason}"))
+}
+
+/// Reads a DER element with `tag` from the start of `der`, returning its content and the
+/// remaining bytes.
+fn read(der: &[u8], tag: u8) -> Result<(&[u8], &[u8])> {
+    match der.split_first() {
+        Some((t, _)) if *t == tag => (),
+        _ => return Err(invalid("unexpected element")),
+    }
+    let (length, der) = match der.get(1) {
+        Some(&length) if length < 0x80 => (length as usize, &der[2..]),
+        // long form, at most 2 length bytes as no valid key document is larger, and only for
+        // lengths that do not fit a shorter form as DER requires
+        Some(&0x81) if der.len() > 2 && der[2] >= 0x80 => (der[2] as usize, &der[3..]),
+        Some(&0x82) if der.len() > 3 && der[2] != 0 => {
+            (u16::from_be_bytes([der[2], der[3]]) as usize, &der[4..])
+        }
+        _ => return Err(invalid("unsupported length")),
+    };
+    if der.len() < length {
+        return Err(invalid("truncated element"));
+    }
+    Ok(der.split_at(length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ED25519_KEY: &str = "d4ee72dbf913584ad5b6d8f1f769f8ad3afe7c28cbf1d4fbe097a88f44755842";
+    const SECP256K1_KEY: &str = "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35";
+
+    fn der(parts: &[&str]) -> Vec<u8> {
+        hex::decode(parts.concat()).unwrap()
+    }
+
+    /// RFC 8410, section 10.3.
+    fn ed25519_v1() -> Vec<u8> {
+        der(&["302e020100300506032b657004220420", ED25519_KEY])
+    }
+
+    /// RFC 8410, section 10.3, with attributes and the public key.
+    fn ed25519_v2() -> Vec<u8> {
+        der(&[
+            "307202010130050603",
+            "2b657004220420",
+            ED25519_KEY,
+            "a01f301d060a2a864886f70d01090914310f0c0d437572646c6520436861697273",
+            "812100",
+            "19bf44096984cdfe8541bac167dc3b96c85086aa30b6b6cb0c5c38ad703166e1",
+        ])
+    }
+
+    /// RFC 5915 with the curve and the public key, whose length needs the long form.
+    fn secp256k1() -> Vec<u8> {
+        der(&[
+            "30818d020100",
+            "301006072a8648ce3d020106052b8104000a",
+            "0476307402010104",
+            "20",
+            SECP256K1_KEY,
+            "a00706052b8104000a",
+            "a14403420004",
+            &"ab".repeat(64),
+        ])
+    }
+
+    #[test]
+    fn reads_ed25519_keys() {
+        let key = hex::decode(ED25519_KEY).unwrap();
+        assert_eq!(private_key(&ed25519_v1(), Algorithm::Ed25519).unwrap(), key);
+        assert_eq!(private_key(&ed25519_v2(), Algorithm::Ed25519).unwrap(), key);
+    }
+
+    #[test]
+    fn reads_secp256k1_keys() {
+        let key = hex::decode(SECP256K1_KEY).unwrap();
+        assert_eq!(
+            private_key(&secp256k1(), Algorithm::Secp256k1).unwrap(),
+            key
+        );
+    }
+
+    #[test]
+    fn rejects_keys_of_other_algorithms() {
+        assert!(private_key(&ed25519_v1(), Algorithm::X25519).is_err());
+        assert!(private_key(&ed25519_v1(), Algorithm::Secp256k1).is_err());
+        assert!(private_key(&secp256k1(), Algorithm::Ed25519).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_documents() {
+        for document in [ed25519_v1(), ed25519_v2(), secp256k1()] {
+            for length in 0..document.len() {
+                assert!(private_key(&document[..length], Algorithm::Ed25519).is_err());
+                assert!(private_key(&document[..length], Algorithm::Secp256k1).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_lengths_beyond_the_document() {
+        let mut document = ed25519_v1();
+        document[1] += 1;
+        assert!(private_key(&document, Algorithm::Ed25519).is_err());
+
+        let mut document = ed25519_v1();
+        document.push(0);
+        assert!(private_key(&document, Algorithm::Ed25519).is_err());
+    }
+
+    #[test]
+    fn rejects_non_minimal_lengths() {
+        let v1 = ed25519_v1();
+        let long_form = [&[0x30, 0x81][..], &v1[1..]].concat();
+        assert!(private_key(&long_form, Algorithm::Ed25519).is_err());
+
+        let longer_form = [&[0x30, 0x82, 0x00][..], &v1[1..]].concat();
+        assert!(private_key(&longer_form, Algorithm::Ed25519).is_err());
+
+        let secp256k1 = secp256k1();
+        let long_form = [&[0x30, 0x82, 0x00][..], &secp256k1[2..]].concat();
+        assert!(private_key(&long_form, Algorithm::Secp256k1).is_err());
+    }
+}
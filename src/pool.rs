@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+
+use crate::stronghold::Result;
+
+/// Limits how many CPU-heavy operations (password hashing, snapshot commits)
+/// run at the same time on the async runtime's blocking threads.
+#[derive(Clone)]
+pub(crate) struct BlockingPool(Arc<Semaphore>);
+
+impl BlockingPool {
+    pub fn new(size: usize) -> Self {
+        Self(Arc::new(Semaphore::new(size.max(1))))
+    }
+
+    /// Runs `f` on a blocking thread once one of the pool's slots is free.
+    ///
+    /// The slot is awaited before the blocking thread is spawned, so queued operations do not
+    /// occupy blocking threads while they wait.
+    pub async fn run<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T> + Send + 'static,
+    {
+        let permit = self
+            .0
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the blocking pool semaphore is never closed");
+        tauri::async_runtime::spawn_blocking(move || {
+            let _permit = permit;
+            f()
+        })
+        .await?
+    }
+}
+
+impl Default for BlockingPool {
+    fn default() -> Self {
+        Self::new(
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+        )
+    }
+}
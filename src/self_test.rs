@@ -0,0 +1,140 @@
+use iota_stronghold::{
+    procedures::{
+        AeadCipher, AeadEncrypt, Ed25519Sign, Hmac, KeyType, PublicKey, Sha2Hash,
+        StrongholdProcedure,
+    },
+    Client, Location,
+};
+use serde::Serialize;
+
+use crate::stronghold::Result;
+
+const VAULT: &[u8] = b"self-test";
+
+/// The outcome of one known-answer test of [`run`].
+#[derive(Serialize)]
+pub(crate) struct SelfTestResult {
+    algorithm: &'static str,
+    passed: bool,
+    /// Why the test could not be run, if it failed with an error rather than a wrong answer.
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct SelfTestReport {
+    passed: bool,
+    results: Vec<SelfTestResult>,
+}
+
+/// Runs known-answer tests of the algorithms used by the plugin in a throwaway stronghold,
+/// using the vault procedures where Stronghold has one.
+pub(crate) fn run() -> Result<SelfTestReport> {
+    let stronghold = iota_stronghold::Stronghold::default();
+    let client = stronghold.create_client(b"tauri-plugin-stronghold:self-test")?;
+
+    let results = vec![
+        check("SHA-256", sha256()),
+        check("HMAC-SHA-256", hmac_sha256(&client)),
+        check("Ed25519", ed25519(&client)),
+        check("AES-256-GCM", aes_256_gcm(&client)),
+    ];
+    Ok(SelfTestReport {
+        passed: results.iter().all(|r| r.passed),
+        results,
+    })
+}
+
+fn check(algorithm: &'static str, result: Result<bool>) -> SelfTestResult {
+    match result {
+        Ok(passed) => SelfTestResult {
+            algorithm,
+            passed,
+            error: None,
+        },
+        Err(e) => SelfTestResult {
+            algorithm,
+            passed: false,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+fn write_key(client: &Client, record: &[u8], key: Vec<u8>) -> Result<Location> {
+    let location = Location::generic(VAULT, record);
+    client.vault(VAULT).write_secret(location.clone(), key)?;
+    Ok(location)
+}
+
+/// FIPS 180-2, appendix B.1.
+fn sha256() -> Result<bool> {
+    use crypto::hashes::{sha::Sha256, Digest};
+
+    let digest = Sha256::digest(b"abc");
+    Ok(digest[..]
+        == hex::decode("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+            .unwrap_or_default())
+}
+
+/// RFC 4231, test case 2.
+fn hmac_sha256(client: &Client) -> Result<bool> {
+    let key = write_key(client, b"hmac", b"Jefe".to_vec())?;
+    let mac: Vec<u8> = client
+        .execute_procedure(StrongholdProcedure::Hmac(Hmac {
+            hash_type: Sha2Hash::Sha256,
+            msg: b"what do ya want for nothing?".to_vec(),
+            key,
+        }))?
+        .into();
+    Ok(mac
+        == hex::decode("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+            .unwrap_or_default())
+}
+
+/// RFC 8032, section 7.1, test 1.
+fn ed25519(client: &Client) -> Result<bool> {
+    let secret = hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+        .unwrap_or_default();
+    let key = write_key(client, b"ed25519", secret)?;
+    let public_key: Vec<u8> = client
+        .execute_procedure(StrongholdProcedure::PublicKey(PublicKey {
+            ty: KeyType::Ed25519,
+            private_key: key.clone(),
+        }))?
+        .into();
+    let signature: Vec<u8> = client
+        .execute_procedure(StrongholdProcedure::Ed25519Sign(Ed25519Sign {
+            private_key: key,
+            msg: Vec::new(),
+        }))?
+        .into();
+    Ok(public_key
+        == hex::decode("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+            .unwrap_or_default()
+        && signature
+            == hex::decode(concat!(
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155",
+                "5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"
+            ))
+            .unwrap_or_default())
+}
+
+/// Test case 14 of the GCM specification.
+fn aes_256_gcm(client: &Client) -> Result<bool> {
+    let key = write_key(client, b"aes-256-gcm", vec![0u8; 32])?;
+    let output: Vec<u8> = client
+        .execute_procedure(StrongholdProcedure::AeadEncrypt(AeadEncrypt {
+            cipher: AeadCipher::Aes256Gcm,
+            associated_data: Vec::new(),
+            plaintext: vec![0u8; 16],
+            nonce: vec![0u8; 12],
+            key,
+        }))?
+        .into();
+    Ok(match crate::split_aead_output(&output) {
+        Some((tag, ciphertext)) => {
+            ciphertext == hex::decode("cea7403d4d606b6e074ec5d3baf39d18").unwrap_or_default()
+                && tag == hex::decode("d0d1c8a799996bf0265b98b5d48ab919").unwrap_or_default()
+        }
+        None => false,
+    })
+}
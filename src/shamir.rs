@@ -0,0 +1,183 @@
+use zeroize::Zeroizing;
+
+use crate::stronghold::{Error, Result};
+
+/// Splits `secret` into `shares` Shamir shares over GF(2^8), any `threshold` of which
+/// reconstruct it with [`combine`].
+///
+/// Each share is its x coordinate, from 1 to `shares`, followed by one byte per byte of `secret`.
+pub(crate) fn split(secret: &[u8], threshold: u8, shares: u8) -> Result<Vec<Vec<u8>>> {
+    if threshold == 0 || threshold > shares {
+        return Err(Error::InvalidShares(format!(
+            "the threshold must be between 1 and the number of shares, got {threshold} of {shares}"
+        )));
+    }
+    if secret.is_empty() {
+        return Err(Error::InvalidShares("the secret is empty".into()));
+    }
+
+    let mut output = (1..=shares).map(|x| vec![x]).collect::<Vec<_>>();
+    // the constant term of each polynomial is a secret byte, the other coefficients are random
+    let mut coefficients = Zeroizing::new(vec![0u8; threshold as usize]);
+    for &byte in secret {
+        coefficients[0] = byte;
+        crypto::utils::rand::fill(&mut coefficients[1..])?;
+        for share in output.iter_mut() {
+            let x = share[0];
+            // Horner's method
+            let y = coefficients
+                .iter()
+                .rev()
+                .fold(0, |y, &coefficient| mul(y, x) ^ coefficient);
+            share.push(y);
+        }
+    }
+    Ok(output)
+}
+
+/// Reconstructs the secret from at least the threshold number of shares created by [`split`].
+///
+/// Fewer shares than the threshold yield a wrong secret rather than an error, as the threshold
+/// is not recorded in the shares.
+pub(crate) fn combine(shares: &[Vec<u8>]) -> Result<Vec<u8>> {
+    let length = match shares.first() {
+        None => return Err(Error::InvalidShares("no shares given".into())),
+        Some(share) if share.len() < 2 => {
+            return Err(Error::InvalidShares(
+                "a share must hold its x coordinate and at least one byte".into(),
+            ))
+        }
+        Some(share) => share.len(),
+    };
+    if shares.iter().any(|share| share.len() != length) {
+        return Err(Error::InvalidShares("the shares differ in length".into()));
+    }
+    let xs = shares.iter().map(|share| share[0]).collect::<Vec<_>>();
+    for (i, x) in xs.iter().enumerate() {
+        if *x == 0 || xs[..i].contains(x) {
+            return Err(Error::InvalidShares(
+                "the shares must have distinct, non-zero x coordinates".into(),
+            ));
+        }
+    }
+
+    // Lagrange interpolation at x = 0, where subtraction is xor
+    let mut secret = vec![0u8; length - 1];
+    for (i, share) in shares.iter().enumerate() {
+        let mut basis = 1;
+        for (j, &x) in xs.iter().enumerate() {
+            if i != j {
+                basis = mul(basis, div(x, x ^ xs[i]));
+            }
+        }
+        for (byte, &y) in secret.iter_mut().zip(&share[1..]) {
+            *byte ^= mul(y, basis);
+        }
+    }
+    Ok(secret)
+}
+
+/// Multiplication in GF(2^8) with the AES polynomial, without data dependent branches.
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+    product
+}
+
+/// Division in GF(2^8), `b` must not be zero.
+fn div(a: u8, b: u8) -> u8 {
+    // b^254 is the inverse of b
+    let mut inverse = 1;
+    let mut power = b;
+    for _ in 0..7 {
+        power = mul(power, power);
+        inverse = mul(inverse, power);
+    }
+    mul(a, inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn division_inverts_multiplication() {
+        for a in 0..=255 {
+            for b in 1..=255 {
+                assert_eq!(div(mul(a, b), b), a);
+            }
+        }
+    }
+
+    #[test]
+    fn combine_known_vector() {
+        // f(x) = [0x53, 0x00, 0xff] + [0xca, 0x01, 0x10] x
+        let shares = vec![
+            vec![1, 0x99, 0x01, 0xef],
+            vec![2, 0xdc, 0x02, 0xdf],
+            vec![3, 0x16, 0x03, 0xcf],
+        ];
+        assert_eq!(combine(&shares[..2]).unwrap(), [0x53, 0x00, 0xff]);
+        assert_eq!(combine(&shares[1..]).unwrap(), [0x53, 0x00, 0xff]);
+        assert_eq!(combine(&shares).unwrap(), [0x53, 0x00, 0xff]);
+    }
+
+    #[test]
+    fn any_threshold_shares_reconstruct_the_secret() {
+        let secret = (0..32).collect::<Vec<u8>>();
+        let shares = split(&secret, 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+        for i in 0..5 {
+            for j in i + 1..5 {
+                for k in j + 1..5 {
+                    let subset = [shares[i].clone(), shares[j].clone(), shares[k].clone()];
+                    assert_eq!(combine(&subset).unwrap(), secret);
+                }
+            }
+        }
+        assert_eq!(combine(&shares).unwrap(), secret);
+    }
+
+    #[test]
+    fn fewer_shares_than_the_threshold_give_a_wrong_secret() {
+        let secret = (0..32).collect::<Vec<u8>>();
+        let shares = split(&secret, 3, 5).unwrap();
+        assert_ne!(combine(&shares[..2]).unwrap(), secret);
+    }
+
+    #[test]
+    fn single_share_of_threshold_one_is_the_secret() {
+        let shares = split(b"secret", 1, 3).unwrap();
+        assert_eq!(combine(&shares[1..2]).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn invalid_shares_are_rejected() {
+        let shares = split(b"secret", 2, 3).unwrap();
+        let duplicate = [shares[0].clone(), shares[0].clone()];
+        assert!(matches!(combine(&duplicate), Err(Error::InvalidShares(_))));
+
+        let mut zero = shares[0].clone();
+        zero[0] = 0;
+        assert!(matches!(
+            combine(&[zero, shares[1].clone()]),
+            Err(Error::InvalidShares(_))
+        ));
+
+        let short = [shares[0].clone(), shares[1][..3].to_vec()];
+        assert!(matches!(combine(&short), Err(Error::InvalidShares(_))));
+
+        assert!(matches!(combine(&[]), Err(Error::InvalidShares(_))));
+        assert!(matches!(combine(&[vec![1]]), Err(Error::InvalidShares(_))));
+        assert!(matches!(
+            split(b"secret", 4, 3),
+            Err(Error::InvalidShares(_))
+        ));
+        assert!(matches!(split(b"", 2, 3), Err(Error::InvalidShares(_))));
+    }
+}
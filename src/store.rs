@@ -0,0 +1,207 @@
+use std::time::Duration;
+
+use iota_stronghold::{
+    procedures::{AeadCipher, AeadDecrypt, AeadEncrypt, StrongholdProcedure},
+    Client, Location,
+};
+
+use crate::stronghold::{Error, Result, Stronghold};
+
+/// Prefix of the store entries marking the value at the rest of the key as encrypted by
+/// [`StoreEncryption`], so values written before it was enabled are still read as is.
+pub(crate) const ENCRYPTED_MARKER_PREFIX: &[u8] = b"tauri-plugin-stronghold:store-encrypted:";
+
+/// The vault and record of the data key of each client.
+const DATA_KEY_VAULT: &[u8] = b"tauri-plugin-stronghold:store";
+const DATA_KEY_RECORD: &[u8] = b"data-key";
+
+const NONCE_SIZE: usize = 24;
+
+/// Encrypts store values with a random data key of each client, kept in a reserved vault,
+/// on top of the encryption of the snapshot.
+///
+/// The data key is not derived from the snapshot key and is never rotated; it only changes with
+/// the client, e.g. when the client is removed.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct StoreEncryption(pub bool);
+
+fn data_key() -> Location {
+    Location::generic(DATA_KEY_VAULT, DATA_KEY_RECORD)
+}
+
+fn marker(key: &[u8]) -> Vec<u8> {
+    [ENCRYPTED_MARKER_PREFIX, key].concat()
+}
+
+impl StoreEncryption {
+    /// Writes `value` to the store of `client` at `key`, encrypted if store encryption is enabled,
+    /// and returns the value it replaces, decrypted.
+    ///
+    /// The data key of `client` is generated on first use.
+    pub fn insert(
+        &self,
+        stronghold: &Stronghold,
+        client: &Client,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        lifetime: Option<Duration>,
+    ) -> Result<Option<Vec<u8>>> {
+        let store = client.store();
+        let was_encrypted = store.contains_key(&marker(&key))?;
+        let previous = if self.0 {
+            let location = data_key();
+            if !client.record_exists(&location)? {
+                // check again under the lock, a concurrent write may have created the key
+                // meanwhile and replacing it would make the values it encrypted unreadable
+                let _guard = stronghold.data_key_lock();
+                if !client.record_exists(&location)? {
+                    let mut data_key = vec![0u8; 32];
+                    crypto::utils::rand::fill(&mut data_key)?;
+                    client
+                        .vault(location.vault_path())
+                        .write_secret(location.clone(), data_key)?;
+                }
+            }
+            let sealed = seal(client, location, &key, value)?;
+            let previous = store.insert(key.clone(), sealed, lifetime)?;
+            store.insert(marker(&key), Vec::new(), None)?;
+            previous
+        } else {
+            let previous = store.insert(key.clone(), value, lifetime)?;
+            store.delete(&marker(&key))?;
+            previous
+        };
+        previous
+            .map(|value| read(client, &key, value, was_encrypted))
+            .transpose()
+    }
+}
+
+/// Reads the value at `key` from the store of `client`, decrypting it if it was encrypted by
+/// [`StoreEncryption`], whether or not it is still enabled.
+pub(crate) fn get(client: &Client, key: &[u8]) -> Result<Option<Vec<u8>>> {
+    let store = client.store();
+    let encrypted = store.contains_key(&marker(key))?;
+    store
+        .get(key)?
+        .map(|value| read(client, key, value, encrypted))
+        .transpose()
+}
+
+/// Removes the value at `key` from the store of `client` and returns it, decrypted.
+pub(crate) fn delete(client: &Client, key: &[u8]) -> Result<Option<Vec<u8>>> {
+    let store = client.store();
+    let encrypted = store.delete(&marker(key))?.is_some();
+    store
+        .delete(key)?
+        .map(|value| read(client, key, value, encrypted))
+        .transpose()
+}
+
+fn read(client: &Client, key: &[u8], value: Vec<u8>, encrypted: bool) -> Result<Vec<u8>> {
+    if encrypted {
+        open(client, data_key(), key, &value)
+    } else {
+        Ok(value)
+    }
+}
+
+/// Encrypts `plaintext` with XChaCha20-Poly1305 under the key at `key`, bound to the store key
+/// `store_key` so it cannot be moved to another entry, returning the nonce followed by the
+/// output of the procedure.
+fn seal(client: &Client, key: Location, store_key: &[u8], plaintext: Vec<u8>) -> Result<Vec<u8>> {
+    let mut nonce = vec![0u8; NONCE_SIZE];
+    crypto::utils::rand::fill(&mut nonce)?;
+    let output: Vec<u8> = client
+        .execute_procedure(StrongholdProcedure::AeadEncrypt(AeadEncrypt {
+            cipher: AeadCipher::XChaCha20Poly1305,
+            associated_data: store_key.to_vec(),
+            plaintext,
+            nonce: nonce.clone(),
+            key,
+        }))?
+        .into();
+
+    let mut sealed = nonce;
+    sealed.extend(output);
+    Ok(sealed)
+}
+
+/// Decrypts a value encrypted by [`seal`] with the key at `key` for the store key `store_key`.
+fn open(client: &Client, key: Location, store_key: &[u8], sealed: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_SIZE {
+        return Err(Error::InvalidSealedValue);
+    }
+    let (nonce, output) = sealed.split_at(NONCE_SIZE);
+    let (tag, ciphertext) = crate::split_aead_output(output).ok_or(Error::InvalidSealedValue)?;
+    let plaintext = client.execute_procedure(StrongholdProcedure::AeadDecrypt(AeadDecrypt {
+        cipher: AeadCipher::XChaCha20Poly1305,
+        associated_data: store_key.to_vec(),
+        ciphertext: ciphertext.to_vec(),
+        tag: tag.to_vec(),
+        nonce: nonce.to_vec(),
+        key,
+    }))?;
+    Ok(plaintext.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TempSnapshot, PASSWORD};
+
+    fn insert(stronghold: &Stronghold, client: &Client, key: &[u8], value: &[u8]) {
+        StoreEncryption(true)
+            .insert(stronghold, client, key.to_vec(), value.to_vec(), None)
+            .unwrap();
+    }
+
+    #[test]
+    fn encrypted_values_are_read_back() {
+        let snapshot = TempSnapshot::new();
+        let stronghold = Stronghold::new(snapshot.path(), PASSWORD.to_vec()).unwrap();
+        let client = stronghold.create_client(b"client").unwrap();
+        insert(&stronghold, &client, b"key", b"value");
+
+        assert_ne!(client.store().get(b"key").unwrap().unwrap(), b"value");
+        assert_eq!(get(&client, b"key").unwrap().unwrap(), b"value");
+        let previous = StoreEncryption(false)
+            .insert(
+                &stronghold,
+                &client,
+                b"key".to_vec(),
+                b"plain".to_vec(),
+                None,
+            )
+            .unwrap();
+        assert_eq!(previous.unwrap(), b"value");
+        assert_eq!(get(&client, b"key").unwrap().unwrap(), b"plain");
+        assert_eq!(delete(&client, b"key").unwrap().unwrap(), b"plain");
+    }
+
+    #[test]
+    fn swapped_values_are_rejected() {
+        let snapshot = TempSnapshot::new();
+        let stronghold = Stronghold::new(snapshot.path(), PASSWORD.to_vec()).unwrap();
+        let client = stronghold.create_client(b"client").unwrap();
+        insert(&stronghold, &client, b"a", b"value of a");
+        insert(&stronghold, &client, b"b", b"value of b");
+
+        let store = client.store();
+        let a = store.get(b"a").unwrap().unwrap();
+        store.insert(b"b".to_vec(), a, None).unwrap();
+        assert!(get(&client, b"b").is_err());
+    }
+
+    #[test]
+    fn plaintext_values_are_never_decrypted() {
+        let snapshot = TempSnapshot::new();
+        let stronghold = Stronghold::new(snapshot.path(), PASSWORD.to_vec()).unwrap();
+        let client = stronghold.create_client(b"client").unwrap();
+        let value = [ENCRYPTED_MARKER_PREFIX, &[0u8; 64]].concat();
+        StoreEncryption(false)
+            .insert(&stronghold, &client, b"key".to_vec(), value.clone(), None)
+            .unwrap();
+        assert_eq!(get(&client, b"key").unwrap().unwrap(), value);
+    }
+}
@@ -1,7 +1,35 @@
-use std::{convert::TryFrom, ops::Deref, path::Path};
+use std::{
+    collections::BTreeSet,
+    convert::TryFrom,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-use iota_stronghold::{KeyProvider, SnapshotPath};
-use serde::{Serialize, Serializer};
+use iota_stronghold::{Client, ClientError, KeyProvider, SnapshotPath};
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+/// The permissions set on snapshot files by default: read and write for the owner only.
+pub const DEFAULT_FILE_MODE: u32 = 0o600;
+
+/// Domain separation for [`Stronghold::key_fingerprint`], so the fingerprint is not a plain hash of the key.
+const FINGERPRINT_CONTEXT: &[u8] = b"tauri-plugin-stronghold:key-fingerprint";
+
+/// The number of bytes of the hash kept in a key fingerprint.
+const FINGERPRINT_LENGTH: usize = 8;
+
+/// Domain separation for the password check stored next to the snapshot, see [`Stronghold::check_password`].
+const PASSWORD_CHECK_CONTEXT: &[u8] = b"tauri-plugin-stronghold:password-check";
+
+/// The number of bytes of the hash kept in a password check.
+const PASSWORD_CHECK_LENGTH: usize = 16;
+
+/// The delay between two attempts of a failed save, see [`Stronghold::with_commit_retries`].
+const COMMIT_RETRY_DELAY: Duration = Duration::from_millis(200);
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -9,12 +37,98 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     #[error("stronghold not initialized")]
     StrongholdNotInitialized,
+    #[error("no client given and no default client configured")]
+    ClientNotSpecified,
+    #[error("invalid key derivation configuration: {0}")]
+    InvalidKdfConfig(String),
+    #[error("the password hash function does not support a custom salt")]
+    SaltNotSupported,
+    #[error("the password hash function does not support changing its parameters")]
+    KdfUpgradeNotSupported,
+    #[error("the `{0}` feature of the plugin is not enabled")]
+    FeatureNotEnabled(&'static str),
+    #[error("the salt does not match the one stored for this snapshot")]
+    SaltMismatch,
+    #[error("unsupported key size {0}")]
+    InvalidKeySize(usize),
+    #[error("invalid seed length {0}, expected 16 to 64 bytes")]
+    InvalidSeedLength(usize),
+    #[error("invalid number of one-time password digits {0}, expected 6 to 8")]
+    InvalidOtpDigits(u32),
+    #[error("invalid gap limit {0}, expected 1 to 1000")]
+    InvalidGapLimit(u32),
+    #[error("invalid chain index {0}, expected an index below 2^31 to be hardened")]
+    InvalidChainIndex(u32),
+    #[error("invalid key shares: {0}")]
+    InvalidShares(String),
+    #[error("no password or key shares given")]
+    PasswordRequired,
+    #[error("invalid private key: {0}")]
+    InvalidPrivateKey(String),
+    #[error("invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+    #[error("unsupported algorithm {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("the value is too short to have been encrypted by the plugin")]
+    InvalidSealedValue,
+    #[error("invalid cursor: {0}")]
+    InvalidCursor(String),
+    #[error("invalid limit {0}, expected at least 1")]
+    InvalidLimit(usize),
+    #[error("failed to access file {path:?}: {reason}")]
+    FileAccess { path: PathBuf, reason: String },
+    #[error("snapshot {0:?} is locked, initialize it again to unlock it")]
+    Locked(PathBuf),
+    #[error("a snapshot already exists at {0:?}")]
+    SnapshotExists(PathBuf),
+    #[error("snapshot {0:?} is still being loaded, try again")]
+    Busy(PathBuf),
+    #[error("snapshot path {0:?} must not leave the namespace directory")]
+    InvalidSnapshotPath(PathBuf),
+    #[error("snapshot {0:?} was never saved")]
+    SnapshotNotPersisted(PathBuf),
+    #[error("snapshot {0:?} was never completely saved or the password is wrong")]
+    IncompleteSnapshot(PathBuf),
+    #[error("failed to write snapshot to {path:?}: {reason}")]
+    SnapshotWriteFailed { path: PathBuf, reason: String },
+    #[error("procedure {step} ({procedure}) failed: {message}")]
+    ProcedureStep {
+        step: usize,
+        procedure: String,
+        message: String,
+    },
     #[error(transparent)]
     Stronghold(#[from] iota_stronghold::ClientError),
     #[error(transparent)]
     Memory(#[from] iota_stronghold::MemoryError),
     #[error(transparent)]
     Procedure(#[from] iota_stronghold::procedures::ProcedureError),
+    #[error(transparent)]
+    Crypto(#[from] crypto::Error),
+    #[error(transparent)]
+    Tauri(#[from] tauri::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// The hash used for key fingerprints, see [`Stronghold::key_fingerprint`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+/// The outcome of checking a snapshot for an interrupted save.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SnapshotRecovery {
+    /// No interrupted save was found.
+    NotNeeded,
+    /// The new snapshot was completely written and has replaced the previous one.
+    Completed,
+    /// The new snapshot was only partially written and has been discarded.
+    RolledBack,
 }
 
 impl Serialize for Error {
@@ -22,7 +136,20 @@ impl Serialize for Error {
     where
         S: Serializer,
     {
-        serializer.serialize_str(self.to_string().as_str())
+        match self {
+            Self::ProcedureStep {
+                step,
+                procedure,
+                message,
+            } => {
+                let mut s = serializer.serialize_struct("ProcedureStep", 3)?;
+                s.serialize_field("step", step)?;
+                s.serialize_field("procedure", procedure)?;
+                s.serialize_field("message", message)?;
+                s.end()
+            }
+            _ => serializer.serialize_str(self.to_string().as_str()),
+        }
     }
 }
 
@@ -30,37 +157,723 @@ pub struct Stronghold {
     inner: iota_stronghold::Stronghold,
     path: SnapshotPath,
     keyprovider: KeyProvider,
+    file_mode: u32,
+    commit_retries: u32,
+    /// Held for reading by bulk reads and for writing by saves and reloads,
+    /// so a read never observes a snapshot being swapped out underneath it.
+    commit_lock: RwLock<()>,
+    /// The paths of the clients created or loaded through [`Self::create_client`]
+    /// and [`Self::load_client`].
+    clients: Mutex<BTreeSet<Vec<u8>>>,
+    /// Whether changes were made since the last save, see [`Self::mark_dirty`].
+    dirty: AtomicBool,
+    /// Set once the in-memory state was discarded by [`Self::lock`].
+    locked: AtomicBool,
+    /// Set by [`Self::create_new`] until the first save, which then records a new creation time
+    /// even though a snapshot already exists.
+    recreated: AtomicBool,
+    last_used: Mutex<Instant>,
+    /// Serializes the creation of the store data keys of the clients, see [`Self::data_key_lock`].
+    data_key_lock: Mutex<()>,
 }
 
 impl Stronghold {
     pub fn new<P: AsRef<Path>>(path: P, password: Vec<u8>) -> Result<Self> {
-        let path = SnapshotPath::from_path(path);
-        let stronghold = iota_stronghold::Stronghold::default();
-        let keyprovider = KeyProvider::try_from(password)?;
-        if path.exists() {
-            stronghold.load_snapshot(&keyprovider, &path)?;
+        let stronghold = Self::empty(path, password, false)?;
+        recover(&stronghold.path, &stronghold.keyprovider)?;
+        if stronghold.path.exists() {
+            stronghold
+                .inner
+                .load_snapshot(&stronghold.keyprovider, &stronghold.path)?;
         }
+        Ok(stronghold)
+    }
+
+    /// Starts an empty stronghold for `path`, ignoring any snapshot already stored there,
+    /// which is overwritten on the next save.
+    ///
+    /// The files stored next to the snapshot are kept until then, and the salt and KDF parameters
+    /// among them are used to derive the password hash; remove them with
+    /// [`Self::remove_snapshot`] first to start without them.
+    pub fn create_new<P: AsRef<Path>>(path: P, password: Vec<u8>) -> Result<Self> {
+        Self::empty(path, password, true)
+    }
+
+    fn empty<P: AsRef<Path>>(path: P, password: Vec<u8>, recreated: bool) -> Result<Self> {
         Ok(Self {
-            inner: stronghold,
-            path,
+            inner: iota_stronghold::Stronghold::default(),
+            path: SnapshotPath::from_path(path),
+            keyprovider: KeyProvider::try_from(password)?,
+            file_mode: DEFAULT_FILE_MODE,
+            commit_retries: 0,
+            commit_lock: RwLock::new(()),
+            clients: Default::default(),
+            dirty: AtomicBool::new(false),
+            locked: AtomicBool::new(false),
+            recreated: AtomicBool::new(recreated),
+            last_used: Mutex::new(Instant::now()),
+            data_key_lock: Mutex::new(()),
+        })
+    }
+
+    /// Wraps a stronghold created elsewhere, e.g. by the host application.
+    ///
+    /// Unlike [`Self::new`] this does not load the snapshot at `path`; it is only used to save
+    /// `inner` with `keyprovider`.
+    pub fn with_existing<P: AsRef<Path>>(
+        inner: iota_stronghold::Stronghold,
+        path: P,
+        keyprovider: KeyProvider,
+    ) -> Self {
+        Self {
+            inner,
+            path: SnapshotPath::from_path(path),
             keyprovider,
+            file_mode: DEFAULT_FILE_MODE,
+            commit_retries: 0,
+            commit_lock: RwLock::new(()),
+            clients: Default::default(),
+            dirty: AtomicBool::new(false),
+            locked: AtomicBool::new(false),
+            recreated: AtomicBool::new(false),
+            last_used: Mutex::new(Instant::now()),
+            data_key_lock: Mutex::new(()),
+        }
+    }
+
+    /// Sets the unix permissions applied to the snapshot file after each save.
+    /// Has no effect on other platforms.
+    pub fn with_file_mode(mut self, mode: u32) -> Self {
+        self.file_mode = mode;
+        self
+    }
+
+    /// Sets how many times a failed save is retried before the error is returned,
+    /// to ride out transient storage failures. Defaults to no retries.
+    pub fn with_commit_retries(mut self, retries: u32) -> Self {
+        self.commit_retries = retries;
+        self
+    }
+
+    /// A short fingerprint of the snapshot key computed with `hash`, as uppercase hex, to let users
+    /// check they unlocked the snapshot with the expected key. The key cannot be recovered from it.
+    pub fn key_fingerprint(&self, hash: HashAlgorithm) -> Result<String> {
+        use crypto::hashes::{
+            sha::{Sha256, Sha384, Sha512},
+            Digest,
+        };
+
+        fn fingerprint<D: Digest>(key: &[u8]) -> String {
+            let mut hasher = D::new();
+            hasher.update(FINGERPRINT_CONTEXT);
+            hasher.update(key);
+            hex::encode_upper(&hasher.finalize()[..FINGERPRINT_LENGTH])
+        }
+
+        let key = self.keyprovider.try_unlock()?;
+        let key = &*key.borrow();
+        Ok(match hash {
+            HashAlgorithm::Sha256 => fingerprint::<Sha256>(key),
+            HashAlgorithm::Sha384 => fingerprint::<Sha384>(key),
+            HashAlgorithm::Sha512 => fingerprint::<Sha512>(key),
         })
     }
 
+    /// Checks the snapshot at `path` for a save that was interrupted, e.g. by a crash,
+    /// and either completes it or discards the partially written file.
+    ///
+    /// This also happens when a snapshot is loaded with [`Self::new`]. If the first save of the
+    /// snapshot was interrupted, or the password is wrong, this fails with
+    /// [`Error::IncompleteSnapshot`] and the snapshot can only be started over with
+    /// [`Self::create_new`].
+    pub fn recover<P: AsRef<Path>>(path: P, password: Vec<u8>) -> Result<SnapshotRecovery> {
+        let keyprovider = KeyProvider::try_from(password)?;
+        recover(&SnapshotPath::from_path(path), &keyprovider)
+    }
+
+    /// Deletes the snapshot at `path` along with the files stored next to it, if they exist.
+    pub fn remove_snapshot<P: AsRef<Path>>(path: P) -> Result<()> {
+        let path = path.as_ref();
+        for path in SIDECAR_EXTENSIONS
+            .into_iter()
+            .map(|extension| sidecar_path(path, extension))
+            .chain([path.to_path_buf()])
+        {
+            if path.is_file() {
+                std::fs::remove_file(&path).map_err(|e| Error::SnapshotWriteFailed {
+                    path,
+                    reason: e.to_string(),
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Compares the password hash with the check stored next to the snapshot at `path` on each
+    /// save, which is much faster than decrypting a large snapshot.
+    ///
+    /// The check is derived from the password hash, not the password, so it does not make
+    /// guessing the password any cheaper. Returns `None` if the snapshot was never saved with
+    /// a check.
+    pub fn check_password<P: AsRef<Path>>(path: P, password: Vec<u8>) -> Result<Option<bool>> {
+        let check_path = password_check_path(path.as_ref());
+        if !check_path.is_file() {
+            return Ok(None);
+        }
+        let stored = std::fs::read(&check_path).map_err(|e| Error::FileAccess {
+            path: check_path,
+            reason: e.to_string(),
+        })?;
+        let password = zeroize::Zeroizing::new(password);
+        Ok(Some(constant_time_eq(&stored, &password_check(&password))))
+    }
+
+    /// Loads the snapshot at `path` into a separate stronghold, without checking it for an
+    /// interrupted save, so the snapshot can be inspected without being modified.
+    pub fn load_detached<P: AsRef<Path>>(
+        path: P,
+        password: Vec<u8>,
+    ) -> Result<iota_stronghold::Stronghold> {
+        let path = SnapshotPath::from_path(path);
+        if !path.exists() {
+            return Err(Error::SnapshotNotPersisted(path.as_path().to_path_buf()));
+        }
+        let keyprovider = KeyProvider::try_from(password)?;
+        let stronghold = iota_stronghold::Stronghold::default();
+        stronghold.load_snapshot(&keyprovider, &path)?;
+        Ok(stronghold)
+    }
+
+    /// When the snapshot at `path` was first saved, as stored next to it on that save.
+    ///
+    /// Falls back to the creation time of the snapshot file for snapshots saved before the
+    /// timestamp was stored, which fails on platforms and file systems without it.
+    pub fn created_at<P: AsRef<Path>>(path: P) -> Result<SystemTime> {
+        let path = path.as_ref();
+        let created_path = created_at_path(path);
+        if created_path.is_file() {
+            let millis = std::fs::read_to_string(&created_path)
+                .map_err(|e| e.to_string())
+                .and_then(|s| s.trim().parse::<u64>().map_err(|e| e.to_string()))
+                .map_err(|reason| Error::FileAccess {
+                    path: created_path,
+                    reason,
+                })?;
+            return Ok(UNIX_EPOCH + Duration::from_millis(millis));
+        }
+        if !path.exists() {
+            return Err(Error::SnapshotNotPersisted(path.to_path_buf()));
+        }
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.created())
+            .map_err(|e| Error::FileAccess {
+                path: path.to_path_buf(),
+                reason: e.to_string(),
+            })
+    }
+
+    /// Writes the snapshot to a temporary file first and moves it over the snapshot once complete,
+    /// so an interrupted save never leaves a damaged snapshot behind.
+    ///
+    /// Does nothing once the stronghold is locked, as its state was saved when it was locked.
     pub fn save(&self) -> Result<()> {
+        let _commit = self
+            .commit_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.save_guarded(self.commit_retries)
+    }
+
+    /// Same as [`Self::save`] without retrying a failed save, for callers that cannot wait for
+    /// the storage to recover, e.g. while the app is exiting.
+    pub fn save_once(&self) -> Result<()> {
+        let _commit = self
+            .commit_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.save_guarded(0)
+    }
+
+    /// Same as [`Self::save`] with `retries` retries, for callers already holding the commit
+    /// lock for writing.
+    fn save_guarded(&self, retries: u32) -> Result<()> {
+        if self.is_locked() {
+            return Ok(());
+        }
+        let dirty = self.dirty.swap(false, Ordering::SeqCst);
+        let mut attempt = 0;
+        loop {
+            match self.write_snapshot() {
+                Err(e) if attempt < retries => {
+                    attempt += 1;
+                    log::debug!(
+                        "failed to save stronghold {:?}, retrying ({}/{}): {}",
+                        self.path.as_path(),
+                        attempt,
+                        retries,
+                        e
+                    );
+                    std::thread::sleep(COMMIT_RETRY_DELAY);
+                }
+                result => {
+                    if result.is_err() && dirty {
+                        self.dirty.store(true, Ordering::SeqCst);
+                    }
+                    return result;
+                }
+            }
+        }
+    }
+
+    /// Writes the state to a snapshot at `path` without saving the stronghold: its snapshot,
+    /// the files stored next to it and whether it has unsaved changes are left untouched.
+    pub fn commit_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let _reading = self.read_guard();
+        if self.is_locked() {
+            return Err(Error::Locked(self.path.as_path().to_path_buf()));
+        }
+        self.inner
+            .commit_with_keyprovider(&SnapshotPath::from_path(path.as_ref()), &self.keyprovider)
+            .map_err(|e| Error::SnapshotWriteFailed {
+                path: path.as_ref().to_path_buf(),
+                reason: e.to_string(),
+            })
+    }
+
+    fn write_snapshot(&self) -> Result<()> {
+        let temp_path = temp_path(self.path.as_path());
         self.inner
-            .commit_with_keyprovider(&self.path, &self.keyprovider)?;
+            .commit_with_keyprovider(&SnapshotPath::from_path(&temp_path), &self.keyprovider)
+            .map_err(|e| self.write_failed(e))?;
+        self.restrict_permissions(&temp_path)?;
+        let created = !self.path.exists() || self.recreated.load(Ordering::SeqCst);
+        std::fs::rename(&temp_path, self.path.as_path()).map_err(|e| self.write_failed(e))?;
+
+        let check_path = password_check_path(self.path.as_path());
+        let key = self.keyprovider.try_unlock()?;
+        let check = password_check(&key.borrow());
+        std::fs::write(&check_path, check).map_err(|e| self.write_failed(e))?;
+        self.restrict_permissions(&check_path)?;
+
+        if created {
+            let created_path = created_at_path(self.path.as_path());
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            std::fs::write(&created_path, now.as_millis().to_string())
+                .map_err(|e| self.write_failed(e))?;
+            self.restrict_permissions(&created_path)?;
+            self.recreated.store(false, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn restrict_permissions(&self, path: &Path) -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(self.file_mode))
+            .map_err(|e| self.write_failed(e))
+    }
+
+    #[cfg(not(unix))]
+    fn restrict_permissions(&self, _path: &Path) -> Result<()> {
+        Ok(())
+    }
+
+    fn write_failed(&self, e: impl std::fmt::Display) -> Error {
+        Error::SnapshotWriteFailed {
+            path: self.path.as_path().to_path_buf(),
+            reason: e.to_string(),
+        }
+    }
+
+    /// Saves the snapshot, then re-encrypts it with the `new` password hash.
+    ///
+    /// `current` must be the password hash the snapshot is saved with, which is checked by
+    /// loading the saved snapshot with it. The returned [`Stronghold`] replaces `self`, which is
+    /// locked so it never saves the snapshot with the old password hash again.
+    pub fn change_password(&self, current: Vec<u8>, new: Vec<u8>) -> Result<Self> {
+        if self.is_locked() {
+            return Err(Error::Locked(self.path.as_path().to_path_buf()));
+        }
+        self.save()?;
+        Self::new(self.path.as_path(), current)?;
+        let changed = self.sharing_state(self.path.as_path(), KeyProvider::try_from(new)?);
+        changed.save()?;
+        // the state is shared with the new stronghold, so it must not be cleared like `lock` does
+        self.locked.store(true, Ordering::SeqCst);
+        Ok(changed)
+    }
+
+    /// Saves the snapshot, then moves it to `new_path` along with the files stored next to it,
+    /// e.g. its salt. The returned [`Stronghold`] replaces `self`, which is locked.
+    ///
+    /// The snapshot is written anew at `new_path` rather than moved, so this works across file
+    /// systems. Fails if a snapshot exists at `new_path`, unless `overwrite` is set.
+    pub fn relocate<P: AsRef<Path>>(&self, new_path: P, overwrite: bool) -> Result<Self> {
+        let old_path = self.path.as_path();
+        let new_path = new_path.as_ref();
+        if self.is_locked() {
+            return Err(Error::Locked(old_path.to_path_buf()));
+        }
+        if new_path.exists() && !overwrite {
+            return Err(Error::SnapshotExists(new_path.to_path_buf()));
+        }
+        self.save()?;
+
+        // copied before the first save at the new path, which would record a new creation time,
+        // and removed if the old snapshot has none so an overwritten snapshot leaves none behind;
+        // the other sidecars are written by the save
+        for extension in ["salt", "kdf", "kdf.new", "created"] {
+            let from = sidecar_path(old_path, extension);
+            let to = sidecar_path(new_path, extension);
+            let copied = if from.is_file() {
+                std::fs::copy(&from, &to).map(|_| ())
+            } else if to.is_file() {
+                std::fs::remove_file(&to)
+            } else {
+                Ok(())
+            };
+            copied.map_err(|e| Error::SnapshotWriteFailed {
+                path: to,
+                reason: e.to_string(),
+            })?;
+        }
+        let key = self.keyprovider.try_unlock()?;
+        let key = zeroize::Zeroizing::new(key.borrow().to_vec());
+        let relocated = self.sharing_state(new_path, KeyProvider::try_from(key.to_vec())?);
+        relocated.save()?;
+
+        // the snapshot is saved at the new path, failing to clean up the old one is not fatal
+        self.locked.store(true, Ordering::SeqCst);
+        for path in SIDECAR_EXTENSIONS
+            .into_iter()
+            .map(|extension| sidecar_path(old_path, extension))
+            .chain([old_path.to_path_buf()])
+        {
+            if path.is_file() {
+                if let Err(e) = std::fs::remove_file(&path) {
+                    log::warn!("failed to remove {:?} after relocating it: {}", path, e);
+                }
+            }
+        }
+        Ok(relocated)
+    }
+
+    /// A stronghold sharing the state of `self`, saved at `path` with `keyprovider`.
+    fn sharing_state(&self, path: &Path, keyprovider: KeyProvider) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            path: SnapshotPath::from_path(path),
+            keyprovider,
+            file_mode: self.file_mode,
+            commit_retries: self.commit_retries,
+            commit_lock: RwLock::new(()),
+            clients: Mutex::new(
+                self.clients
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone(),
+            ),
+            dirty: AtomicBool::new(false),
+            locked: AtomicBool::new(false),
+            recreated: AtomicBool::new(false),
+            last_used: Mutex::new(Instant::now()),
+            data_key_lock: Mutex::new(()),
+        }
+    }
+
+    /// Discards the in-memory state and loads the snapshot from disk again.
+    pub fn reload(&self) -> Result<()> {
+        if !self.path.exists() {
+            return Err(Error::SnapshotNotPersisted(
+                self.path.as_path().to_path_buf(),
+            ));
+        }
+        let _commit = self
+            .commit_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if self.is_locked() {
+            return Err(Error::Locked(self.path.as_path().to_path_buf()));
+        }
+        self.inner.clear()?;
+        self.clients
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        self.inner.load_snapshot(&self.keyprovider, &self.path)?;
+        self.dirty.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Records that the in-memory state differs from the saved snapshot.
+    pub fn mark_dirty(&self) {
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether changes were made since the snapshot was last saved or loaded.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.load(Ordering::SeqCst)
+    }
+
+    /// Saves pending changes and discards the in-memory state to free its memory.
+    ///
+    /// A locked stronghold cannot be used anymore, the snapshot must be loaded again instead.
+    pub fn lock(&self) -> Result<()> {
+        // held across the save and the clear, so a write in between cannot be lost
+        let _commit = self
+            .commit_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if self.is_dirty() {
+            self.save_guarded(self.commit_retries)?;
+        }
+        self.locked.store(true, Ordering::SeqCst);
+        self.clients
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        self.inner.clear()?;
         Ok(())
     }
 
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::SeqCst)
+    }
+
+    /// Records an access, see [`Self::last_used`].
+    pub fn touch(&self) {
+        *self
+            .last_used
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Instant::now();
+    }
+
+    /// When the stronghold was last accessed by a command, used to pick the strongholds to lock
+    /// when too many are loaded.
+    pub fn last_used(&self) -> Instant {
+        *self
+            .last_used
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Blocks saves and reloads until the returned guard is dropped.
+    ///
+    /// Take this around reads spanning several records so they never see the state being
+    /// reloaded. Writes to vaults and stores do not take the lock, so a guarded read can still
+    /// see the changes of concurrent writers.
+    pub fn read_guard(&self) -> RwLockReadGuard<'_, ()> {
+        self.commit_lock
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Held while a store data key is created, so concurrent writes never create two keys for
+    /// the same client.
+    pub(crate) fn data_key_lock(&self) -> MutexGuard<'_, ()> {
+        self.data_key_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn create_client<P: AsRef<[u8]>>(&self, client_path: P) -> Result<Client, ClientError> {
+        let client = self.inner.create_client(client_path.as_ref())?;
+        self.clients
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(client_path.as_ref().to_vec());
+        Ok(client)
+    }
+
+    pub fn load_client<P: AsRef<[u8]>>(&self, client_path: P) -> Result<Client, ClientError> {
+        let client = self.inner.load_client(client_path.as_ref())?;
+        self.clients
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(client_path.as_ref().to_vec());
+        Ok(client)
+    }
+
+    /// The paths of the clients currently loaded, in lexicographic order.
+    ///
+    /// Stronghold does not expose the clients of a snapshot, so this only includes the clients
+    /// created or loaded through this wrapper since the snapshot was last (re)loaded.
+    pub fn clients(&self) -> Vec<Vec<u8>> {
+        self.clients
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .cloned()
+            .collect()
+    }
+
     pub fn inner(&self) -> &iota_stronghold::Stronghold {
         &self.inner
     }
 }
 
+fn temp_path(path: &Path) -> PathBuf {
+    sidecar_path(path, "tmp")
+}
+
+fn password_check_path(path: &Path) -> PathBuf {
+    sidecar_path(path, "check")
+}
+
+fn created_at_path(path: &Path) -> PathBuf {
+    sidecar_path(path, "created")
+}
+
+/// The extensions of all files stored next to a snapshot, see [`sidecar_path`].
+pub(crate) const SIDECAR_EXTENSIONS: [&str; 6] =
+    ["tmp", "check", "created", "salt", "kdf", "kdf.new"];
+
+/// The path of a file stored next to the snapshot, e.g. `vault.hold.check`.
+pub(crate) fn sidecar_path(path: &Path, extension: &str) -> PathBuf {
+    let mut sidecar_path = path.as_os_str().to_owned();
+    sidecar_path.push(".");
+    sidecar_path.push(extension);
+    sidecar_path.into()
+}
+
+fn password_check(key: &[u8]) -> Vec<u8> {
+    use crypto::hashes::{sha::Sha256, Digest};
+
+    let mut hasher = Sha256::new();
+    hasher.update(PASSWORD_CHECK_CONTEXT);
+    hasher.update(key);
+    hasher.finalize()[..PASSWORD_CHECK_LENGTH].to_vec()
+}
+
+/// Compares `a` and `b` in time independent of their contents, for values derived from secrets.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let difference = a
+        .iter()
+        .zip(b)
+        .fold(0u8, |difference, (a, b)| difference | (a ^ b));
+    a.len() == b.len() && difference == 0
+}
+
+fn recover(path: &SnapshotPath, keyprovider: &KeyProvider) -> Result<SnapshotRecovery> {
+    let temp_path = temp_path(path.as_path());
+    if !temp_path.exists() {
+        return Ok(SnapshotRecovery::NotNeeded);
+    }
+    let load = |path: &SnapshotPath| {
+        iota_stronghold::Stronghold::default().load_snapshot(keyprovider, path)
+    };
+    let write_failed = |e: std::io::Error| Error::SnapshotWriteFailed {
+        path: path.as_path().to_path_buf(),
+        reason: e.to_string(),
+    };
+
+    // a fully written temporary snapshot means only the final rename was interrupted
+    let temp_snapshot = SnapshotPath::from_path(&temp_path);
+    if let Err(e) = load(&temp_snapshot) {
+        // with a wrong password neither file loads, so the temporary one is only discarded
+        // once the current snapshot is known to be intact
+        if path.exists() {
+            load(path)?;
+        } else {
+            // a partially written first save cannot be told apart from a wrong password, so it is
+            // kept until the snapshot is created anew with `Stronghold::create_new`
+            log::debug!("failed to load {:?}: {}", temp_path, e);
+            return Err(Error::IncompleteSnapshot(path.as_path().to_path_buf()));
+        }
+        std::fs::remove_file(&temp_path).map_err(write_failed)?;
+        return Ok(SnapshotRecovery::RolledBack);
+    }
+
+    std::fs::rename(&temp_path, path.as_path()).map_err(write_failed)?;
+    Ok(SnapshotRecovery::Completed)
+}
+
 impl Deref for Stronghold {
     type Target = iota_stronghold::Stronghold;
     fn deref(&self) -> &Self::Target {
         &self.inner
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use super::*;
+    use crate::testing::{TempSnapshot, PASSWORD};
+
+    const CLIENT: &[u8] = b"client";
+    const ENTRIES: usize = 20;
+
+    fn client(stronghold: &Stronghold) -> Client {
+        stronghold
+            .get_client(CLIENT)
+            .or_else(|_| stronghold.load_client(CLIENT))
+            // loaded by another thread in the meantime
+            .or_else(|_| stronghold.get_client(CLIENT))
+            .unwrap()
+    }
+
+    #[test]
+    fn guarded_reads_never_see_a_reload() {
+        let snapshot = TempSnapshot::new();
+        let stronghold = Arc::new(Stronghold::new(snapshot.path(), PASSWORD.to_vec()).unwrap());
+        let store = stronghold.create_client(CLIENT).unwrap().store();
+        for i in 0..ENTRIES {
+            store.insert(vec![i as u8], vec![0], None).unwrap();
+        }
+        stronghold.save().unwrap();
+
+        let writer = {
+            let stronghold = stronghold.clone();
+            thread::spawn(move || {
+                for i in 0..50u32 {
+                    let key = [b"extra:".as_slice(), &i.to_be_bytes()].concat();
+                    client(&stronghold)
+                        .store()
+                        .insert(key, vec![1], None)
+                        .unwrap();
+                    stronghold.mark_dirty();
+                    stronghold.save().unwrap();
+                    stronghold.reload().unwrap();
+                }
+            })
+        };
+
+        while !writer.is_finished() {
+            let _reading = stronghold.read_guard();
+            let store = client(&stronghold).store();
+            let keys = store.keys().unwrap();
+            for i in 0..ENTRIES {
+                assert!(keys.contains(&vec![i as u8]), "read during a reload");
+                assert_eq!(store.get(&[i as u8]).unwrap(), Some(vec![0]));
+            }
+        }
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn interrupted_first_save_can_be_started_over() {
+        let snapshot = TempSnapshot::new();
+        let stronghold = Stronghold::new(snapshot.path(), PASSWORD.to_vec()).unwrap();
+        stronghold.create_client(CLIENT).unwrap();
+        stronghold.save().unwrap();
+        // the first save was cut off halfway through writing the temporary file
+        let written = std::fs::read(snapshot.path()).unwrap();
+        std::fs::write(temp_path(snapshot.path()), &written[..written.len() / 2]).unwrap();
+        std::fs::remove_file(snapshot.path()).unwrap();
+
+        assert!(matches!(
+            Stronghold::new(snapshot.path(), PASSWORD.to_vec()),
+            Err(Error::IncompleteSnapshot(_))
+        ));
+
+        let stronghold = Stronghold::create_new(snapshot.path(), PASSWORD.to_vec()).unwrap();
+        stronghold.create_client(CLIENT).unwrap();
+        stronghold.save().unwrap();
+        let stronghold = Stronghold::new(snapshot.path(), PASSWORD.to_vec()).unwrap();
+        assert!(stronghold.load_client(CLIENT).is_ok());
+    }
+}
@@ -0,0 +1,95 @@
+//! Helpers for writing tests against the plugin commands.
+//!
+//! A collection built with [`seeded_collection`] can be handed to [`tauri::Builder::manage`]
+//! before the plugin is registered, in which case the plugin uses it instead of an empty one.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError},
+    time::{Duration, Instant},
+};
+
+use iota_stronghold::Location;
+
+use crate::{
+    stronghold::{sidecar_path, Result, Stronghold, SIDECAR_EXTENSIONS},
+    StrongholdCollection,
+};
+
+/// The already hashed password used by [`seeded_collection`].
+pub const PASSWORD: &[u8; 32] = b"tauri-plugin-stronghold-testing!";
+
+/// A snapshot path in the temporary directory that is removed on drop, along with the files
+/// stored next to it.
+pub struct TempSnapshot(PathBuf);
+
+impl TempSnapshot {
+    pub fn new() -> Self {
+        let mut id = [0u8; 8];
+        crypto::utils::rand::fill(&mut id).expect("failed to generate snapshot name");
+        Self(std::env::temp_dir().join(format!("stronghold-{}.hold", hex::encode(id))))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Default for TempSnapshot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TempSnapshot {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+        for extension in SIDECAR_EXTENSIONS {
+            let _ = std::fs::remove_file(sidecar_path(&self.0, extension));
+        }
+    }
+}
+
+/// A secret written to the vault by [`seeded_collection`].
+pub struct Record {
+    pub client: Vec<u8>,
+    pub location: Location,
+    pub secret: Vec<u8>,
+}
+
+/// Creates a collection holding a stronghold for `snapshot` unlocked with [`PASSWORD`],
+/// with the given records written to their clients.
+pub fn seeded_collection(
+    snapshot: &TempSnapshot,
+    records: Vec<Record>,
+) -> Result<StrongholdCollection> {
+    let stronghold = Stronghold::new(snapshot.path(), PASSWORD.to_vec())?;
+    for record in records {
+        let client = stronghold
+            .get_client(&record.client)
+            .or_else(|_| stronghold.create_client(&record.client))?;
+        client
+            .vault(record.location.vault_path())
+            .write_secret(record.location, record.secret)?;
+    }
+
+    let collection = StrongholdCollection::default();
+    collection
+        .0
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(snapshot.path().to_owned(), Arc::new(stronghold));
+    Ok(collection)
+}
+
+/// Times a commit of `stronghold` to a scratch snapshot in the temporary directory, to tune the
+/// KDF and commit debounce settings for the device.
+///
+/// The stronghold is not saved, so its snapshot and unsaved changes are left as they are.
+/// Fails with [`Error::Locked`](crate::stronghold::Error::Locked) if it is locked.
+pub fn time_commit(stronghold: &Stronghold) -> Result<Duration> {
+    let scratch = TempSnapshot::new();
+    let start = Instant::now();
+    stronghold.commit_to(scratch.path())?;
+    Ok(start.elapsed())
+}